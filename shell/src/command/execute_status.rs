@@ -10,6 +10,7 @@ use strum_macros::{Display, EnumString, FromRepr};
 pub enum ShExecuteStatus {
     Done = 0,
     Running = 1,
+    Failed = 2,
 }
 
 #[derive(GodotClass)]
@@ -21,4 +22,6 @@ impl ExecuteStatus {
     pub const DONE: u8 = ShExecuteStatus::Done as u8;
     #[constant]
     pub const RUNNING: u8 = ShExecuteStatus::Running as u8;
+    #[constant]
+    pub const FAILED: u8 = ShExecuteStatus::Failed as u8;
 }
@@ -0,0 +1,111 @@
+use super::IInternalCommand;
+use crate::{
+    command::execute_status::ShExecuteStatus,
+    shell::SHELL,
+    utils::{ansi_string::rust::ShAnsiString, color256::Color256, tokenizer::quote},
+};
+use godot::builtin::{Array, GString};
+use tmui::tlib::nonnull_mut;
+
+/// `alias [name[=value]...]`, define or display the aliases.
+///
+/// Without parameters all the aliases are listed.
+pub struct CmdAlias;
+
+impl CmdAlias {
+    #[inline]
+    fn format_alias(echo: ShAnsiString, name: &str, value: &str) -> ShAnsiString {
+        echo.foreground_256(Color256::CYAN)
+            .append(name)
+            .clear_style()
+            .append("=")
+            .append(&quote(value))
+            .crlf()
+    }
+}
+
+impl IInternalCommand for CmdAlias {
+    #[inline]
+    fn command_name(&self) -> String {
+        "alias".to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        "Define the aliases, display the alias when `=value` is omitted, or list all the aliases without parameters.".to_string()
+    }
+
+    #[inline]
+    fn usage(&self) -> String {
+        "alias [name[=value]...]".to_string()
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec![
+            r#"alias gm="godmode on && give all""#.to_string(),
+            "alias gm".to_string(),
+        ]
+    }
+
+    fn start(&mut self, params: Array<GString>) -> ShExecuteStatus {
+        let mut echo = ShAnsiString::new();
+        let mut failed = false;
+
+        SHELL.with(|rf| {
+            let shell = nonnull_mut!(rf.borrow_mut());
+            if params.is_empty() {
+                for (name, value) in shell.aliases() {
+                    echo = Self::format_alias(std::mem::take(&mut echo), name, value);
+                }
+                return;
+            }
+
+            for param in params.iter_shared() {
+                let param = param.to_string();
+                match param.split_once('=') {
+                    Some((name, value)) => {
+                        if !shell.set_alias(name, value) {
+                            echo = std::mem::take(&mut echo)
+                                .foreground_256(Color256::RED)
+                                .append(&format!("`{}` is not a valid alias name.", name))
+                                .clear_style()
+                                .crlf();
+                            failed = true;
+                        }
+                    }
+                    None => match shell.get_alias(&param) {
+                        Some(value) => {
+                            echo = Self::format_alias(std::mem::take(&mut echo), &param, value)
+                        }
+                        None => {
+                            echo = std::mem::take(&mut echo)
+                                .foreground_256(Color256::RED)
+                                .append(&format!("Alias `{}` not found.", param))
+                                .clear_style()
+                                .crlf();
+                            failed = true;
+                        }
+                    },
+                }
+            }
+        });
+        self.echo(echo);
+
+        if failed {
+            self.fail(1)
+        } else {
+            ShExecuteStatus::Done
+        }
+    }
+
+    fn complete(&mut self, _params: Array<GString>, _index: usize) -> Array<GString> {
+        SHELL.with(|rf| {
+            nonnull_mut!(rf.borrow_mut())
+                .aliases()
+                .into_iter()
+                .map(|(name, _)| GString::from(name.as_str()))
+                .collect()
+        })
+    }
+}
@@ -4,17 +4,29 @@ use godot::builtin::{Array, GString};
 
 pub struct CmdCls;
 
+impl CmdCls {
+    /// Clear the entire screen and move the cursor to the top left.
+    #[inline]
+    pub fn clear_screen() -> ShAnsiString {
+        ShAnsiString::default()
+            .clear_entire_screen()
+            .cursor_move_to(1, 1)
+    }
+}
+
 impl IInternalCommand for CmdCls {
     #[inline]
     fn command_name(&self) -> String {
         "cls".to_string()
     }
 
+    #[inline]
+    fn description(&self) -> String {
+        "Clear entire screen.".to_string()
+    }
+
     fn start(&mut self, _: Array<GString>) -> ShExecuteStatus {
-        let echo = ShAnsiString::default()
-            .clear_entire_screen()
-            .cursor_move_to(1, 1);
-        self.echo(echo);
+        self.echo(Self::clear_screen());
 
         ShExecuteStatus::Done
     }
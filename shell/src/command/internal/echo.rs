@@ -0,0 +1,34 @@
+use super::IInternalCommand;
+use crate::{command::execute_status::ShExecuteStatus, utils::ansi_string::rust::ShAnsiString};
+use godot::builtin::{Array, GString};
+
+pub struct CmdEcho;
+
+impl IInternalCommand for CmdEcho {
+    #[inline]
+    fn command_name(&self) -> String {
+        "echo".to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        "Print the parameters.".to_string()
+    }
+
+    #[inline]
+    fn usage(&self) -> String {
+        "echo [text...]".to_string()
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec!["echo $?".to_string(), r#"echo "Hello, world!""#.to_string()]
+    }
+
+    fn start(&mut self, params: Array<GString>) -> ShExecuteStatus {
+        let text: Vec<String> = params.iter_shared().map(|p| p.to_string()).collect();
+        self.echo(ShAnsiString::new().append(&text.join(" ")).crlf());
+
+        ShExecuteStatus::Done
+    }
+}
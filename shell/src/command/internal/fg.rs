@@ -0,0 +1,101 @@
+use super::{
+    IInternalCommand,
+    jobs::{job_ids, parse_job_id},
+};
+use crate::{
+    command::execute_status::ShExecuteStatus,
+    shell::{Job, SHELL},
+    utils::{ansi_string::rust::ShAnsiString, color256::Color256},
+};
+use godot::builtin::{Array, Dictionary, GString};
+use tmui::tlib::nonnull_mut;
+
+/// `fg [id]`, bring the background job to the foreground.
+///
+/// The job is ticked by `fg` until it finished, so it can be interrupted by Control+C and receive the keys.
+#[derive(Default)]
+pub struct CmdFg {
+    job: Option<Job>,
+}
+
+impl IInternalCommand for CmdFg {
+    #[inline]
+    fn command_name(&self) -> String {
+        "fg".to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        "Bring the background job to the foreground, the latest job if the id is omitted."
+            .to_string()
+    }
+
+    #[inline]
+    fn usage(&self) -> String {
+        "fg [id]".to_string()
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec!["fg".to_string(), "fg 2".to_string()]
+    }
+
+    fn start(&mut self, params: Array<GString>) -> ShExecuteStatus {
+        let take_job = |id| SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).take_job(id));
+        let param = params.get(0).map(|p| p.to_string());
+        self.job = match param.as_deref() {
+            Some(param) => parse_job_id(param).and_then(|id| take_job(Some(id))),
+            None => take_job(None),
+        };
+
+        let Some(job) = self.job.as_ref() else {
+            let error = match param {
+                Some(param) => format!("fg: no such job `{}`.", param),
+                None => "fg: no current job.".to_string(),
+            };
+            self.echo(
+                ShAnsiString::new()
+                    .foreground_256(Color256::RED)
+                    .append(&error)
+                    .clear_style()
+                    .crlf(),
+            );
+            return self.fail(1);
+        };
+
+        self.echo(ShAnsiString::new().append(&job.line).crlf());
+        ShExecuteStatus::Running
+    }
+
+    #[inline]
+    fn running(&mut self) -> ShExecuteStatus {
+        let Some(job) = self.job.as_ref() else {
+            return ShExecuteStatus::Done;
+        };
+
+        let status = job.command.running();
+        if status != ShExecuteStatus::Running {
+            self.job = None;
+        }
+        status
+    }
+
+    #[inline]
+    fn interrupting(&mut self) {
+        if let Some(job) = self.job.take() {
+            job.command.interrupting();
+        }
+    }
+
+    #[inline]
+    fn on_key(&mut self, key: Dictionary) {
+        if let Some(job) = self.job.as_ref() {
+            job.command.on_key(key);
+        }
+    }
+
+    #[inline]
+    fn complete(&mut self, _params: Array<GString>, _index: usize) -> Array<GString> {
+        job_ids()
+    }
+}
@@ -0,0 +1,283 @@
+use super::IInternalCommand;
+use crate::{
+    command::{Command, execute_status::ShExecuteStatus},
+    shell::SHELL,
+    utils::{
+        ansi_string::rust::ShAnsiString,
+        color256::Color256,
+        options::{options_rows, options_usage},
+    },
+};
+use godot::{
+    builtin::{Array, GString},
+    obj::Gd,
+};
+use tmui::tlib::nonnull_mut;
+
+/// Width limit of the name column in commands list.
+const MAX_NAME_WIDTH: usize = 24;
+/// Width of the label column in command details.
+const LABEL_WIDTH: usize = 13;
+
+/// Metadata of the internal command or command, displayed by `help`.
+pub struct CommandHelp {
+    pub name: String,
+    pub description: String,
+    pub usage: String,
+    pub examples: Vec<String>,
+    /// Rows of the options table, represent as (flags, description).
+    pub options: Vec<(String, String)>,
+    /// Help of the subcommands, whose names are prefixed by the parent name, e.g. `level load`.
+    pub subcommands: Vec<CommandHelp>,
+}
+
+impl CommandHelp {
+    pub fn from_internal(icmd: &dyn IInternalCommand) -> Self {
+        Self {
+            name: icmd.command_name(),
+            description: icmd.description(),
+            usage: icmd.usage(),
+            examples: icmd.examples(),
+            options: vec![],
+            subcommands: vec![],
+        }
+    }
+
+    pub fn from_command(name: &str, command: &Gd<Command>) -> Self {
+        let command = command.bind();
+        let specs = command.option_specs();
+        let mut usage = command.get_usage().to_string();
+        if usage.is_empty() && !specs.is_empty() {
+            usage = options_usage(name, &specs);
+        }
+
+        Self {
+            name: name.to_string(),
+            description: command.get_description().to_string(),
+            usage,
+            examples: command
+                .get_examples()
+                .iter_shared()
+                .map(|e| e.to_string())
+                .collect(),
+            options: options_rows(&specs),
+            subcommands: command
+                .subcommands()
+                .iter()
+                .map(|sub| {
+                    let name = format!("{} {}", name, sub.bind().get_command_name());
+                    Self::from_command(&name, sub)
+                })
+                .collect(),
+        }
+    }
+
+    /// The first line of description, used as the summary in commands list.
+    #[inline]
+    fn summary(&self) -> &str {
+        self.description.lines().next().unwrap_or_default()
+    }
+
+    /// Collect the help and its subcommands recursively, represent as (depth, help).
+    fn flatten<'a>(&'a self, depth: usize, rows: &mut Vec<(usize, &'a CommandHelp)>) {
+        rows.push((depth, self));
+        for sub in self.subcommands.iter() {
+            sub.flatten(depth + 1, rows);
+        }
+    }
+}
+
+/// `help [command]`, list all the commands, or show the details of the command.
+pub struct CmdHelp;
+
+impl CmdHelp {
+    fn commands_table(mut echo: ShAnsiString, title: &str, helps: &[CommandHelp]) -> ShAnsiString {
+        if helps.is_empty() {
+            return echo;
+        }
+
+        // Subcommands are listed under their parent command with indent.
+        let mut rows = vec![];
+        for help in helps {
+            help.flatten(0, &mut rows);
+        }
+        let width = rows
+            .iter()
+            .map(|(depth, h)| depth * 2 + h.name.len())
+            .max()
+            .unwrap_or_default()
+            .min(MAX_NAME_WIDTH)
+            + 2;
+
+        echo = echo
+            .foreground_256(Color256::YELLOW)
+            .append(title)
+            .clear_style()
+            .crlf();
+        for (depth, help) in rows {
+            echo = echo
+                .space_in(2 + depth * 2)
+                .foreground_256(Color256::GREEN)
+                .append_fixed_text(&help.name, width.saturating_sub(depth * 2))
+                .clear_style()
+                .append(help.summary())
+                .crlf();
+        }
+        echo
+    }
+
+    fn detail_row(mut echo: ShAnsiString, label: &str, lines: &[&str]) -> ShAnsiString {
+        if lines.is_empty() {
+            return echo;
+        }
+
+        echo = echo
+            .foreground_256(Color256::YELLOW)
+            .append_fixed_text(label, LABEL_WIDTH)
+            .clear_style();
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                echo = echo.space_in(LABEL_WIDTH);
+            }
+            echo = echo.append(line).crlf();
+        }
+        echo
+    }
+
+    pub fn command_detail(help: &CommandHelp) -> ShAnsiString {
+        let usage = if help.usage.is_empty() {
+            help.name.as_str()
+        } else {
+            help.usage.as_str()
+        };
+        let description: Vec<&str> = help.description.lines().collect();
+        let examples: Vec<&str> = help.examples.iter().map(|e| e.as_str()).collect();
+        let width = help
+            .options
+            .iter()
+            .map(|(flags, _)| flags.len())
+            .max()
+            .unwrap_or_default();
+        let options: Vec<String> = help
+            .options
+            .iter()
+            .map(|(flags, description)| format!("{:<width$}  {}", flags, description))
+            .collect();
+        let options: Vec<&str> = options.iter().map(|o| o.as_str()).collect();
+        let width = help
+            .subcommands
+            .iter()
+            .map(|sub| sub.name.len())
+            .max()
+            .unwrap_or_default();
+        let subcommands: Vec<String> = help
+            .subcommands
+            .iter()
+            .map(|sub| format!("{:<width$}  {}", sub.name, sub.summary()))
+            .collect();
+        let subcommands: Vec<&str> = subcommands.iter().map(|s| s.as_str()).collect();
+
+        let echo = Self::detail_row(ShAnsiString::new(), "Name", &[&help.name]);
+        let echo = Self::detail_row(echo, "Description", &description);
+        let echo = Self::detail_row(echo, "Usage", &[usage]);
+        let echo = Self::detail_row(echo, "Options", &options);
+        let echo = Self::detail_row(echo, "Subcommands", &subcommands);
+        Self::detail_row(echo, "Examples", &examples)
+    }
+}
+
+impl IInternalCommand for CmdHelp {
+    #[inline]
+    fn command_name(&self) -> String {
+        "help".to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        "List all the commands, or show the details of the command.".to_string()
+    }
+
+    #[inline]
+    fn usage(&self) -> String {
+        "help [command [subcommand...]]".to_string()
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec![
+            "help".to_string(),
+            "help alias".to_string(),
+            "help level load".to_string(),
+        ]
+    }
+
+    fn start(&mut self, params: Array<GString>) -> ShExecuteStatus {
+        let path: Vec<String> = params.iter_shared().map(|p| p.to_string()).collect();
+        if path.is_empty() {
+            let (internal_helps, command_helps) =
+                SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).command_helps());
+
+            let echo =
+                Self::commands_table(ShAnsiString::new(), "Internal commands:", &internal_helps);
+            let echo = Self::commands_table(echo, "Commands:", &command_helps);
+            self.echo(
+                echo.append("Type `help <command>` to show the details of the command.")
+                    .crlf(),
+            );
+            return ShExecuteStatus::Done;
+        }
+
+        let name = path.join(" ");
+        let (help, alias) = SHELL.with(|rf| {
+            let shell = nonnull_mut!(rf.borrow_mut());
+            (shell.command_help(&path), shell.get_alias(&name).cloned())
+        });
+
+        if let Some(help) = help {
+            self.echo(Self::command_detail(&help));
+            ShExecuteStatus::Done
+        } else if let Some(alias) = alias {
+            self.echo(
+                ShAnsiString::new()
+                    .append(&format!("`{}` is an alias of `{}`.", name, alias))
+                    .crlf(),
+            );
+            ShExecuteStatus::Done
+        } else {
+            self.echo(
+                ShAnsiString::new()
+                    .foreground_256(Color256::RED)
+                    .append(&format!("Command `{}` not found.", name))
+                    .clear_style()
+                    .crlf(),
+            );
+            self.fail(1)
+        }
+    }
+
+    fn complete(&mut self, params: Array<GString>, index: usize) -> Array<GString> {
+        if index == 0 {
+            let (internal_helps, command_helps) =
+                SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).command_helps());
+            return internal_helps
+                .iter()
+                .chain(command_helps.iter())
+                .map(|h| GString::from(h.name.as_str()))
+                .collect();
+        }
+
+        let path: Vec<String> = params
+            .iter_shared()
+            .take(index)
+            .map(|p| p.to_string())
+            .collect();
+        let Some(help) = SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).command_help(&path)) else {
+            return Array::new();
+        };
+        help.subcommands
+            .iter()
+            .filter_map(|sub| sub.name.rsplit(' ').next())
+            .map(GString::from)
+            .collect()
+    }
+}
@@ -0,0 +1,63 @@
+use super::IInternalCommand;
+use crate::{
+    command::execute_status::ShExecuteStatus, shell::SHELL, utils::ansi_string::rust::ShAnsiString,
+};
+use godot::builtin::{Array, GString};
+use tmui::tlib::nonnull_mut;
+
+/// `jobs`, list the commands running in the background.
+pub struct CmdJobs;
+
+impl IInternalCommand for CmdJobs {
+    #[inline]
+    fn command_name(&self) -> String {
+        "jobs".to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        concat!(
+            "List the commands running in the background.\n",
+            "A command line ending with `&` starts the last command in the background, ",
+            "its output is tagged with the job id, e.g. `[1]`."
+        )
+        .to_string()
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec!["log &".to_string(), "jobs".to_string()]
+    }
+
+    fn start(&mut self, _params: Array<GString>) -> ShExecuteStatus {
+        let mut echo = ShAnsiString::new();
+        SHELL.with(|rf| {
+            for (id, line) in nonnull_mut!(rf.borrow_mut()).jobs() {
+                echo = std::mem::take(&mut echo)
+                    .append(&format!("[{}] Running  {}", id, line))
+                    .crlf();
+            }
+        });
+        self.echo(echo);
+
+        ShExecuteStatus::Done
+    }
+}
+
+/// Parse the job id parameter of `fg` and `kill`, the `%` prefix is optional, e.g. `1` or `%1`.
+#[inline]
+pub fn parse_job_id(param: &str) -> Option<usize> {
+    param.strip_prefix('%').unwrap_or(param).parse().ok()
+}
+
+/// Ids of the background jobs as the completion candidates.
+#[inline]
+pub fn job_ids() -> Array<GString> {
+    SHELL.with(|rf| {
+        nonnull_mut!(rf.borrow_mut())
+            .jobs()
+            .into_iter()
+            .map(|(id, _)| GString::from(id.to_string().as_str()))
+            .collect()
+    })
+}
@@ -0,0 +1,73 @@
+use super::{
+    IInternalCommand,
+    jobs::{job_ids, parse_job_id},
+};
+use crate::{
+    command::execute_status::ShExecuteStatus,
+    shell::SHELL,
+    utils::{ansi_string::rust::ShAnsiString, color256::Color256},
+};
+use godot::builtin::{Array, GString};
+use tmui::tlib::nonnull_mut;
+
+/// `kill <id...>`, interrupt the background jobs.
+pub struct CmdKill;
+
+impl IInternalCommand for CmdKill {
+    #[inline]
+    fn command_name(&self) -> String {
+        "kill".to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        "Interrupt the background jobs, as Control+C does to the foreground command.".to_string()
+    }
+
+    #[inline]
+    fn usage(&self) -> String {
+        "kill <id...>".to_string()
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec!["kill 1".to_string(), "kill %1 %2".to_string()]
+    }
+
+    fn start(&mut self, params: Array<GString>) -> ShExecuteStatus {
+        if params.is_empty() {
+            self.echo(
+                ShAnsiString::new()
+                    .foreground_256(Color256::RED)
+                    .append(&format!("Usage: {}", self.usage()))
+                    .clear_style()
+                    .crlf(),
+            );
+            return self.fail(2);
+        }
+
+        let mut status = ShExecuteStatus::Done;
+        for param in params.iter_shared() {
+            let param = param.to_string();
+            let killed = parse_job_id(&param)
+                .is_some_and(|id| SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).kill_job(id)));
+            if !killed {
+                self.echo(
+                    ShAnsiString::new()
+                        .foreground_256(Color256::RED)
+                        .append(&format!("kill: no such job `{}`.", param))
+                        .clear_style()
+                        .crlf(),
+                );
+                status = self.fail(1);
+            }
+        }
+
+        status
+    }
+
+    #[inline]
+    fn complete(&mut self, _params: Array<GString>, _index: usize) -> Array<GString> {
+        job_ids()
+    }
+}
@@ -28,6 +28,16 @@ impl IInternalCommand for CmdLog {
         "log".to_string()
     }
 
+    #[inline]
+    fn description(&self) -> String {
+        concat!(
+            "Display the captured Godot output and logs recorded by ",
+            "Termdot.info(), Termdot.warn() and Termdot.error().\n",
+            "Keep running until interrupted by Control+C."
+        )
+        .to_string()
+    }
+
     #[inline]
     fn start(&mut self, _params: Array<GString>) -> ShExecuteStatus {
         ShExecuteStatus::Running
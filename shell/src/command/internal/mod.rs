@@ -1,9 +1,21 @@
+pub mod alias;
 pub mod cls;
+pub mod echo;
+pub mod fg;
+pub mod help;
+pub mod jobs;
+pub mod kill;
 pub mod log;
+pub mod set;
+pub mod source;
+pub mod unalias;
+pub mod unset;
+pub mod vars;
 pub mod version;
+pub mod watch;
 
 use crate::{shell::SHELL, utils::ansi_string::rust::ShAnsiString};
-use godot::builtin::{Array, GString, Vector2i};
+use godot::builtin::{Array, Dictionary, GString, Vector2i};
 use tmui::tlib::nonnull_mut;
 
 use super::execute_status::ShExecuteStatus;
@@ -14,6 +26,23 @@ pub type InternalCommandHnd = *mut dyn IInternalCommand;
 pub trait IInternalCommand {
     fn command_name(&self) -> String;
 
+    /// Description of the command, the first line is used as the summary in `help` list.
+    #[inline]
+    fn description(&self) -> String {
+        String::new()
+    }
+
+    /// Usage of the command parameters, command name is used if it's empty.
+    #[inline]
+    fn usage(&self) -> String {
+        String::new()
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec![]
+    }
+
     fn start(&mut self, params: Array<GString>) -> ShExecuteStatus;
 
     #[inline]
@@ -24,6 +53,20 @@ pub trait IInternalCommand {
     #[inline]
     fn interrupting(&mut self) {}
 
+    #[inline]
+    #[allow(unused_variables)]
+    /// Executed for every key pressed while the command is running with raw input enabled,
+    /// `key` is the same as the one passed to `Command::_on_key()`.
+    fn on_key(&mut self, key: Dictionary) {}
+
+    #[inline]
+    #[allow(unused_variables)]
+    /// Candidates of the parameter at `index` when Tab is pressed, `params` are the parameters typed so far,
+    /// and `params[index]` is the incomplete one. Candidates not starting with it are filtered out by shell.
+    fn complete(&mut self, params: Array<GString>, index: usize) -> Array<GString> {
+        Array::new()
+    }
+
     #[inline]
     /// Get current terminal size, represent as (cols, rows)
     fn get_terminal_size(&self) -> Vector2i {
@@ -42,4 +85,17 @@ pub trait IInternalCommand {
     fn echo(&self, text: ShAnsiString) {
         SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).sh_echo(text))
     }
+
+    #[inline]
+    /// Deliver the keys to [`on_key`](IInternalCommand::on_key) instead of storing them while the command is running.
+    fn set_raw_input(&self, enable: bool) {
+        SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).set_raw_input(enable))
+    }
+
+    #[inline]
+    /// Mark the command as failed with the exit code, return [`ShExecuteStatus::Failed`].
+    fn fail(&self, code: i32) -> ShExecuteStatus {
+        SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).set_exit_code(code));
+        ShExecuteStatus::Failed
+    }
 }
@@ -0,0 +1,79 @@
+use super::IInternalCommand;
+use crate::{
+    command::execute_status::ShExecuteStatus,
+    shell::SHELL,
+    utils::{ansi_string::rust::ShAnsiString, color256::Color256},
+};
+use godot::builtin::{Array, GString};
+use tmui::tlib::nonnull_mut;
+
+/// `set <name> [value...]`, set the shell variable, multiple values are joined by space.
+pub struct CmdSet;
+
+impl IInternalCommand for CmdSet {
+    #[inline]
+    fn command_name(&self) -> String {
+        "set".to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        "Set the shell variable, multiple values are joined by space.".to_string()
+    }
+
+    #[inline]
+    fn usage(&self) -> String {
+        "set <name> [value...]".to_string()
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec![
+            "set pos 10 20".to_string(),
+            r#"set player "Big Orc""#.to_string(),
+        ]
+    }
+
+    fn start(&mut self, params: Array<GString>) -> ShExecuteStatus {
+        let mut params: Vec<String> = params.iter_shared().map(|p| p.to_string()).collect();
+        if params.is_empty() {
+            self.echo(
+                ShAnsiString::new()
+                    .foreground_256(Color256::RED)
+                    .append(&format!("Usage: {}", self.usage()))
+                    .clear_style()
+                    .crlf(),
+            );
+            return self.fail(2);
+        }
+
+        let name = params.remove(0);
+        let value = params.join(" ");
+        if !SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).set_var(&name, &value)) {
+            self.echo(
+                ShAnsiString::new()
+                    .foreground_256(Color256::RED)
+                    .append(&format!("`{}` is not a valid variable name.", name))
+                    .clear_style()
+                    .crlf(),
+            );
+            return self.fail(1);
+        }
+
+        ShExecuteStatus::Done
+    }
+
+    fn complete(&mut self, _params: Array<GString>, index: usize) -> Array<GString> {
+        if index != 0 {
+            return Array::new();
+        }
+
+        SHELL.with(|rf| {
+            nonnull_mut!(rf.borrow_mut())
+                .vars()
+                .into_iter()
+                .map(|(name, _)| GString::from(name.as_str()))
+                .collect()
+        })
+    }
+}
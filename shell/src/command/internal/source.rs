@@ -0,0 +1,91 @@
+use super::IInternalCommand;
+use crate::{
+    command::execute_status::ShExecuteStatus,
+    shell::SHELL,
+    utils::{ansi_string::rust::ShAnsiString, color256::Color256},
+};
+use godot::builtin::{Array, GString};
+use tmui::tlib::nonnull_mut;
+
+/// `source [-e] <path>`, run the command lines of the script file one by one.
+///
+/// Registered as both `source` and `exec`.
+pub struct CmdSource {
+    name: &'static str,
+}
+
+impl CmdSource {
+    #[inline]
+    pub fn new(name: &'static str) -> Self {
+        Self { name }
+    }
+}
+
+impl IInternalCommand for CmdSource {
+    #[inline]
+    fn command_name(&self) -> String {
+        self.name.to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        concat!(
+            "Run the command lines of the script file one by one, lines starting with `#` are comments.\n",
+            "With `-e`, the remaining lines are skipped once a line failed.\n",
+            "Paths without scheme are relative to `user://`."
+        )
+        .to_string()
+    }
+
+    #[inline]
+    fn usage(&self) -> String {
+        format!("{} [-e] <path>", self.name)
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec![
+            format!("{} res://scripts/boss_fight.cfg", self.name),
+            format!("{} -e setup.cfg", self.name),
+        ]
+    }
+
+    fn start(&mut self, params: Array<GString>) -> ShExecuteStatus {
+        let mut params: Vec<String> = params.iter_shared().map(|p| p.to_string()).collect();
+        let stop_on_failure = params.first().is_some_and(|p| p == "-e");
+        if stop_on_failure {
+            params.remove(0);
+        }
+
+        let [path] = params.as_slice() else {
+            self.echo(
+                ShAnsiString::new()
+                    .foreground_256(Color256::RED)
+                    .append(&format!("Usage: {}", self.usage()))
+                    .clear_style()
+                    .crlf(),
+            );
+            return self.fail(2);
+        };
+        let path = if path.contains("://") {
+            path.clone()
+        } else {
+            format!("user://{}", path)
+        };
+
+        if let Err(e) =
+            SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).source(&path, stop_on_failure))
+        {
+            self.echo(
+                ShAnsiString::new()
+                    .foreground_256(Color256::RED)
+                    .append(&format!("{}: {}.", self.name, e))
+                    .clear_style()
+                    .crlf(),
+            );
+            return self.fail(1);
+        }
+
+        ShExecuteStatus::Done
+    }
+}
@@ -0,0 +1,75 @@
+use super::IInternalCommand;
+use crate::{
+    command::execute_status::ShExecuteStatus,
+    shell::SHELL,
+    utils::{ansi_string::rust::ShAnsiString, color256::Color256},
+};
+use godot::builtin::{Array, GString};
+use tmui::tlib::nonnull_mut;
+
+/// `unalias <name...>`, remove the aliases.
+pub struct CmdUnalias;
+
+impl IInternalCommand for CmdUnalias {
+    #[inline]
+    fn command_name(&self) -> String {
+        "unalias".to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        "Remove the aliases.".to_string()
+    }
+
+    #[inline]
+    fn usage(&self) -> String {
+        "unalias <name...>".to_string()
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec!["unalias gm".to_string()]
+    }
+
+    fn start(&mut self, params: Array<GString>) -> ShExecuteStatus {
+        if params.is_empty() {
+            self.echo(
+                ShAnsiString::new()
+                    .foreground_256(Color256::RED)
+                    .append(&format!("Usage: {}", self.usage()))
+                    .clear_style()
+                    .crlf(),
+            );
+            return self.fail(2);
+        }
+
+        let mut echo = ShAnsiString::new();
+        for name in params.iter_shared() {
+            let name = name.to_string();
+            if !SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).unalias(&name)) {
+                echo = echo
+                    .foreground_256(Color256::RED)
+                    .append(&format!("Alias `{}` not found.", name))
+                    .clear_style()
+                    .crlf();
+            }
+        }
+
+        if echo.is_empty() {
+            ShExecuteStatus::Done
+        } else {
+            self.echo(echo);
+            self.fail(1)
+        }
+    }
+
+    fn complete(&mut self, _params: Array<GString>, _index: usize) -> Array<GString> {
+        SHELL.with(|rf| {
+            nonnull_mut!(rf.borrow_mut())
+                .aliases()
+                .into_iter()
+                .map(|(name, _)| GString::from(name.as_str()))
+                .collect()
+        })
+    }
+}
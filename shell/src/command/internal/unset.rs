@@ -0,0 +1,62 @@
+use super::IInternalCommand;
+use crate::{
+    command::execute_status::ShExecuteStatus,
+    shell::SHELL,
+    utils::{ansi_string::rust::ShAnsiString, color256::Color256},
+};
+use godot::builtin::{Array, GString};
+use tmui::tlib::nonnull_mut;
+
+/// `unset <name...>`, remove the shell variables.
+pub struct CmdUnset;
+
+impl IInternalCommand for CmdUnset {
+    #[inline]
+    fn command_name(&self) -> String {
+        "unset".to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        "Remove the shell variables.".to_string()
+    }
+
+    #[inline]
+    fn usage(&self) -> String {
+        "unset <name...>".to_string()
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec!["unset pos".to_string()]
+    }
+
+    fn start(&mut self, params: Array<GString>) -> ShExecuteStatus {
+        if params.is_empty() {
+            self.echo(
+                ShAnsiString::new()
+                    .foreground_256(Color256::RED)
+                    .append(&format!("Usage: {}", self.usage()))
+                    .clear_style()
+                    .crlf(),
+            );
+            return self.fail(2);
+        }
+
+        for name in params.iter_shared() {
+            SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).unset_var(&name.to_string()));
+        }
+
+        ShExecuteStatus::Done
+    }
+
+    fn complete(&mut self, _params: Array<GString>, _index: usize) -> Array<GString> {
+        SHELL.with(|rf| {
+            nonnull_mut!(rf.borrow_mut())
+                .vars()
+                .into_iter()
+                .map(|(name, _)| GString::from(name.as_str()))
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use super::IInternalCommand;
+use crate::{
+    command::execute_status::ShExecuteStatus,
+    shell::SHELL,
+    utils::{ansi_string::rust::ShAnsiString, color256::Color256, tokenizer::quote},
+};
+use godot::builtin::{Array, GString};
+use tmui::tlib::nonnull_mut;
+
+/// `vars`, list all the shell variables.
+pub struct CmdVars;
+
+impl IInternalCommand for CmdVars {
+    #[inline]
+    fn command_name(&self) -> String {
+        "vars".to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        "List all the shell variables.".to_string()
+    }
+
+    fn start(&mut self, _params: Array<GString>) -> ShExecuteStatus {
+        let mut echo = ShAnsiString::new();
+        SHELL.with(|rf| {
+            for (name, value) in nonnull_mut!(rf.borrow_mut()).vars() {
+                echo = std::mem::take(&mut echo)
+                    .foreground_256(Color256::CYAN)
+                    .append(name)
+                    .clear_style()
+                    .append("=")
+                    .append(&quote(value))
+                    .crlf();
+            }
+        });
+        self.echo(echo);
+
+        ShExecuteStatus::Done
+    }
+}
@@ -15,6 +15,11 @@ impl IInternalCommand for CmdVersion {
         "version".to_string()
     }
 
+    #[inline]
+    fn description(&self) -> String {
+        "Show current Termdot version.".to_string()
+    }
+
     #[inline]
     fn start(&mut self, _params: Array<GString>) -> ShExecuteStatus {
         let shell_version = shell_version();
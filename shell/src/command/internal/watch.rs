@@ -0,0 +1,203 @@
+use super::IInternalCommand;
+use crate::{
+    command::execute_status::ShExecuteStatus,
+    shell::{JobCommand, SHELL},
+    utils::{ansi_string::rust::ShAnsiString, color256::Color256},
+};
+use godot::builtin::{Array, GString};
+use std::time::{Duration, Instant};
+use tmui::tlib::nonnull_mut;
+
+/// Interval of `watch` when `-n` is omitted, in seconds.
+const DEFAULT_INTERVAL: f64 = 2.;
+/// Minimum interval of `watch`, in seconds.
+const MIN_INTERVAL: f64 = 0.1;
+
+/// `watch [-n <seconds>] <command...>`, run the command periodically and display its output
+/// in the area below the prompt, until interrupted by Control+C.
+#[derive(Default)]
+pub struct CmdWatch {
+    interval: Duration,
+    /// The watched command line, parsed on every run like a typed line so the variables are up to date.
+    line: String,
+    /// Row of the first line of the area below the prompt, which is cleared on every refresh.
+    origin: i32,
+    last_run: Option<Instant>,
+    /// The watched command returning `RUNNING`, ticked until it finished before its output is displayed.
+    running: Option<JobCommand>,
+    output: String,
+}
+
+impl CmdWatch {
+    /// Scroll the screen if the area below the prompt is less than half of the screen,
+    /// return the first row of the area.
+    fn reserve_area(&self) -> i32 {
+        let rows = self.get_terminal_size().y;
+        let cursor = self.get_cursor_position();
+        let row = if cursor.x == 1 {
+            cursor.y
+        } else {
+            cursor.y + 1
+        };
+
+        let start = rows - rows / 2 + 1;
+        if row <= start {
+            return row;
+        }
+
+        // The line breaks at the bottom scroll the screen up by `row - start` lines.
+        let lines = row - start + rows - cursor.y;
+        let echo = (0..lines).fold(ShAnsiString::new(), |echo, _| echo.crlf());
+        self.echo(echo.cursor_move_to(start, 1));
+        start
+    }
+
+    /// Clear the area and display the header followed by the output of the last run.
+    fn display(&self) {
+        let rows = self.get_terminal_size().y;
+        let origin = self.origin.min(rows);
+
+        let mut echo = ShAnsiString::new()
+            .cursor_move_to(origin, 1)
+            .clear_cursor_to_screen_end()
+            .foreground_256(Color256::BRIGHT_BLACK)
+            .append(&format!(
+                "Every {:?}s: {}",
+                self.interval.as_secs_f64(),
+                self.line
+            ))
+            .clear_style();
+        // The lines out of the screen are cut off, otherwise the screen scrolls and the area moves.
+        for line in self.output.lines().take((rows - origin).max(0) as usize) {
+            echo = echo.crlf().append(line);
+        }
+        self.echo(echo.clear_style());
+    }
+
+    #[inline]
+    fn parse_line(&self) -> Result<Vec<String>, String> {
+        SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).parse_single_command(&self.line))
+    }
+
+    fn usage_error(&self) -> ShExecuteStatus {
+        self.echo(
+            ShAnsiString::new()
+                .foreground_256(Color256::RED)
+                .append(&format!("Usage: {}", self.usage()))
+                .clear_style()
+                .crlf(),
+        );
+        self.fail(2)
+    }
+}
+
+impl IInternalCommand for CmdWatch {
+    #[inline]
+    fn command_name(&self) -> String {
+        "watch".to_string()
+    }
+
+    #[inline]
+    fn description(&self) -> String {
+        concat!(
+            "Run the command every interval and display its output below the prompt, ",
+            "until interrupted by Control+C.\n",
+            "The interval is 2 seconds by default, at least 0.1 seconds.\n",
+            "The words are joined into a command line with aliases and variables expanded on every run, ",
+            "chained and background commands are not supported."
+        )
+        .to_string()
+    }
+
+    #[inline]
+    fn usage(&self) -> String {
+        "watch [-n <seconds>] <command...>".to_string()
+    }
+
+    #[inline]
+    fn examples(&self) -> Vec<String> {
+        vec![
+            "watch enemies count".to_string(),
+            "watch -n 0.5 get player position".to_string(),
+            "watch 'echo $hp'".to_string(),
+        ]
+    }
+
+    fn start(&mut self, params: Array<GString>) -> ShExecuteStatus {
+        let mut words: Vec<String> = params.iter_shared().map(|p| p.to_string()).collect();
+        let mut interval = DEFAULT_INTERVAL;
+        if words.first().is_some_and(|w| w == "-n") {
+            match words
+                .get(1)
+                .and_then(|w| w.parse::<f64>().ok())
+                .filter(|secs| secs.is_finite() && *secs > 0.)
+            {
+                Some(secs) => interval = secs.max(MIN_INTERVAL),
+                None => return self.usage_error(),
+            }
+            words.drain(..2);
+        }
+        if words.is_empty() {
+            return self.usage_error();
+        }
+
+        self.line = words.join(" ");
+        if let Err(e) = self.parse_line() {
+            self.echo(
+                ShAnsiString::new()
+                    .foreground_256(Color256::RED)
+                    .append(&format!("watch: {}.", e))
+                    .clear_style()
+                    .crlf(),
+            );
+            return self.fail(2);
+        }
+
+        self.interval = Duration::from_secs_f64(interval);
+        self.last_run = None;
+        self.running = None;
+        self.output.clear();
+        self.origin = self.reserve_area();
+
+        ShExecuteStatus::Running
+    }
+
+    fn running(&mut self) -> ShExecuteStatus {
+        if let Some(command) = self.running.as_ref() {
+            let (running, output) =
+                SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).tick_captured(command));
+            self.output.push_str(&output);
+            if !running {
+                self.running = None;
+                self.display();
+            }
+            return ShExecuteStatus::Running;
+        }
+
+        if self
+            .last_run
+            .is_some_and(|last_run| last_run.elapsed() < self.interval)
+        {
+            return ShExecuteStatus::Running;
+        }
+        self.last_run = Some(Instant::now());
+
+        let (running, output) = match self.parse_line() {
+            Ok(words) => SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).start_captured(words)),
+            Err(e) => (None, format!("watch: {}.", e)),
+        };
+        self.output = output;
+        self.running = running;
+        if self.running.is_none() {
+            self.display();
+        }
+        ShExecuteStatus::Running
+    }
+
+    #[inline]
+    fn interrupting(&mut self) {
+        if let Some(command) = self.running.take() {
+            SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).interrupt_captured(&command));
+        }
+    }
+}
@@ -1,9 +1,14 @@
 pub mod execute_status;
 pub mod internal;
+pub mod option;
 
-use crate::{shell::SHELL, utils::ansi_string::godot::AnsiString};
+use crate::{
+    shell::{InputKind, SHELL},
+    utils::{ansi_string::godot::AnsiString, options::OptionSpec},
+};
 use execute_status::ShExecuteStatus;
 use godot::prelude::*;
+use option::CommandOption;
 use tmui::tlib::nonnull_mut;
 
 #[derive(GodotClass)]
@@ -14,24 +19,56 @@ pub struct Command {
     /// If it is the same as an internal command, it will be ignored.
     /// If it is the same as the previous command, the previous one will be overwritten.
     command_name: GString,
+    #[export(multiline)]
+    /// Description of the command displayed by internal command `help`,
+    /// the first line is used as the summary in commands list.
+    description: GString,
+    #[export]
+    /// Usage of the command parameters displayed by `help <command>`, e.g. `spawn <name> [count]`.
+    usage: GString,
+    #[export]
+    /// Examples of the command displayed by `help <command>`.
+    examples: Array<GString>,
+    #[export]
+    /// Declared parameters of the command. If it's not empty, the parameters are parsed and validated
+    /// by shell, then passed to `_start_with_options()` instead of `_start()`. Empty slots are ignored.
+    options: Array<Option<Gd<CommandOption>>>,
+    /// Whether the input requested by `read_line()`, `confirm()` or `choose()` is not answered yet.
+    waiting_input: bool,
+    /// Answer of the requested input, taken by `get_input()`.
+    input: Variant,
     base: Base<Node>,
 }
 
 #[allow(unused_variables)]
 #[godot_api]
 impl Command {
+    #[signal]
+    /// Emitted when the input requested by `read_line()`, `confirm()` or `choose()` is answered.
+    fn input_received(answer: Variant);
+
     #[func(virtual, gd_self)]
     /// This method is executed when the command is detected.
-    /// The command is trimmed by spaces, and parameters are passed as `params`.
-    /// Return value: ExecuteStatus.DONE or ExecuteStatus.RUNNING.
+    /// The command line is split into words by whitespace, and parameters are passed as `params`.
+    /// Quotes and backslash escapes keep spaces inside one parameter, e.g. `spawn "Big Orc" 3`.
+    /// Return value: ExecuteStatus.DONE, ExecuteStatus.RUNNING or ExecuteStatus.FAILED.
     pub fn start(gd: Gd<Self>, params: Array<GString>) -> ShExecuteStatus {
         ShExecuteStatus::Done
     }
 
     #[func(virtual, gd_self)]
-    /// This method executes when `_start()` returns `ExecuteStatus.RUNNING` and continues
+    /// This method is executed instead of `_start()` when the command declares `options`.
+    /// `options` maps the option names to the typed values, the ones not given and without default are `null`.
+    /// Invalid parameters are rejected by shell, and `--help` displays the help generated from `options`.
+    /// Return value: ExecuteStatus.DONE, ExecuteStatus.RUNNING or ExecuteStatus.FAILED.
+    pub fn start_with_options(gd: Gd<Self>, options: Dictionary) -> ShExecuteStatus {
+        ShExecuteStatus::Done
+    }
+
+    #[func(virtual, gd_self)]
+    /// This method executes when `_start()` or `_start_with_options()` returns `ExecuteStatus.RUNNING` and continues
     /// running until `_running()` itself returns `ExecuteStatus.DONE`.
-    /// Return value: ExecuteStatus.DONE or ExecuteStatus.RUNNING.
+    /// Return value: ExecuteStatus.DONE, ExecuteStatus.RUNNING or ExecuteStatus.FAILED.
     pub fn running(gd: Gd<Self>) -> ShExecuteStatus {
         ShExecuteStatus::Done
     }
@@ -41,6 +78,21 @@ impl Command {
     /// Do nothing by default.
     pub fn interrupting(gd: Gd<Self>) {}
 
+    #[func(virtual, gd_self)]
+    /// Executed for every key pressed while the command is running with raw input enabled by `set_raw_input(true)`.
+    /// `key` contains `key` (name like `a`, `Enter`, `Up` or `F5`), `text` (the typed text, empty for special keys),
+    /// and the modifiers `ctrl`, `alt` and `shift`.
+    /// Do nothing by default.
+    pub fn on_key(gd: Gd<Self>, key: Dictionary) {}
+
+    #[func(virtual, gd_self)]
+    /// This method is executed when Tab is pressed while typing the parameters of the command.
+    /// `params` are the parameters typed so far, `params[index]` is the one being completed.
+    /// Return value: candidates of the parameter, the ones not starting with `params[index]` are filtered out.
+    pub fn complete(gd: Gd<Self>, params: Array<GString>, index: i32) -> Array<GString> {
+        Array::new()
+    }
+
     #[func]
     /// Get current terminal size, represent as (cols, rows)
     pub fn get_terminal_size(&self) -> Vector2i {
@@ -60,4 +112,118 @@ impl Command {
     pub fn echo(&self, text: Gd<AnsiString>) {
         SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).echo(text))
     }
+
+    #[func]
+    /// Ask the user for a line of text after `prompt`, should be called while the command is running.
+    /// The answer is a `String`, delivered by signal `input_received` and `get_input()`.
+    pub fn read_line(&mut self, prompt: GString) {
+        self.request_input(InputKind::Line, prompt.to_string());
+    }
+
+    #[func]
+    /// Ask the user a yes or no question, answered by `y`/`yes`, anything else means no.
+    /// The answer is a `bool`, delivered by signal `input_received` and `get_input()`.
+    pub fn confirm(&mut self, question: GString) {
+        self.request_input(InputKind::Confirm, format!("{} [y/N] ", question));
+    }
+
+    #[func]
+    /// Ask the user to choose one of the numbered options by its number or text.
+    /// The answer is the `int` index of the option, delivered by signal `input_received` and `get_input()`.
+    pub fn choose(&mut self, options: Array<GString>) {
+        let options: Vec<String> = options.iter_shared().map(|o| o.to_string()).collect();
+        let prompt = format!("Choose [1-{}]: ", options.len());
+        self.request_input(InputKind::Choose(options), prompt);
+    }
+
+    #[func]
+    /// Whether the requested input is not answered yet.
+    pub fn is_waiting_input(&self) -> bool {
+        self.waiting_input
+    }
+
+    #[func]
+    /// Take the answer of the requested input, `null` if it's not answered yet.
+    /// Usually called in `_running()` after `read_line()`, `confirm()` or `choose()`.
+    pub fn get_input(&mut self) -> Variant {
+        std::mem::replace(&mut self.input, Variant::nil())
+    }
+
+    #[func]
+    /// Deliver the keys to `_on_key()` instead of storing them while the command is running,
+    /// reset when the command finished. Control+C still interrupts the command.
+    pub fn set_raw_input(&self, enable: bool) {
+        SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).set_raw_input(enable))
+    }
+
+    #[func]
+    /// Mark the command as failed with the exit code, which can be read by `$?` after the command finished.
+    /// Return value: ExecuteStatus.FAILED, so it can be returned directly, e.g. `return fail(2)`.
+    pub fn fail(&self, code: i32) -> ShExecuteStatus {
+        SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).set_exit_code(code));
+        ShExecuteStatus::Failed
+    }
+}
+
+impl Command {
+    fn request_input(&mut self, kind: InputKind, prompt: String) {
+        self.waiting_input = true;
+        self.input = Variant::nil();
+        let gd = self.to_gd();
+        SHELL.with(|rf| nonnull_mut!(rf.borrow_mut()).request_input(gd, kind, prompt));
+    }
+
+    /// Called by shell when the requested input is answered.
+    pub fn deliver_input(mut gd: Gd<Command>, answer: Variant) {
+        {
+            let mut command = gd.bind_mut();
+            command.waiting_input = false;
+            command.input = answer.clone();
+        }
+        gd.emit_signal("input_received", &[answer]);
+    }
+
+    /// The child Command nodes with non-empty `command_name`, which act as subcommands.
+    pub fn subcommands(&self) -> Vec<Gd<Command>> {
+        self.base()
+            .get_children()
+            .iter_shared()
+            .filter_map(|child| child.try_cast::<Command>().ok())
+            .filter(|sub| !sub.bind().command_name.is_empty())
+            .collect()
+    }
+
+    /// Walk down the subcommands by the leading words,
+    /// return the deepest matched command and the number of words consumed.
+    pub fn resolve(gd: &Gd<Command>, words: &[String]) -> (Gd<Command>, usize) {
+        let mut command = gd.clone();
+        let mut depth = 0;
+        while let Some(word) = words.get(depth) {
+            let sub = command
+                .bind()
+                .subcommands()
+                .into_iter()
+                .find(|sub| sub.bind().command_name == GString::from(word.as_str()));
+            match sub {
+                Some(sub) => command = sub,
+                None => break,
+            }
+            depth += 1;
+        }
+        (command, depth)
+    }
+
+    #[inline]
+    pub fn option_specs(&self) -> Vec<OptionSpec> {
+        self.options
+            .iter_shared()
+            .filter_map(|option| option.map(|option| option.bind().to_spec()))
+            .collect()
+    }
+
+    /// Whether a slot of `options` is added in the inspector but left empty.
+    #[inline]
+    pub fn has_empty_option(&self) -> bool {
+        self.options.iter_shared().any(|option| option.is_none())
+    }
 }
@@ -0,0 +1,122 @@
+use crate::utils::options::{OptionKind, OptionSpec, OptionValue};
+use godot::prelude::*;
+use strum_macros::{Display, EnumString, FromRepr};
+
+#[repr(u8)]
+#[rustfmt::skip]
+#[derive(
+    GodotConvert, Var, Export, Display, Debug, FromRepr, EnumString, PartialEq, Eq, Clone, Copy, PartialOrd, Ord
+)]
+#[godot(via = u8)]
+pub enum ShOptionType {
+    Int = 0,
+    Float = 1,
+    Bool = 2,
+    String = 3,
+    NodePath = 4,
+    Enum = 5,
+}
+
+impl From<ShOptionType> for OptionKind {
+    #[inline]
+    fn from(value: ShOptionType) -> Self {
+        match value {
+            ShOptionType::Int => Self::Int,
+            ShOptionType::Float => Self::Float,
+            ShOptionType::Bool => Self::Bool,
+            ShOptionType::String => Self::String,
+            ShOptionType::NodePath => Self::NodePath,
+            ShOptionType::Enum => Self::Enum,
+        }
+    }
+}
+
+#[derive(GodotClass)]
+#[class(init)]
+pub struct OptionType;
+#[godot_api]
+impl OptionType {
+    #[constant]
+    pub const INT: u8 = ShOptionType::Int as u8;
+    #[constant]
+    pub const FLOAT: u8 = ShOptionType::Float as u8;
+    #[constant]
+    pub const BOOL: u8 = ShOptionType::Bool as u8;
+    #[constant]
+    pub const STRING: u8 = ShOptionType::String as u8;
+    #[constant]
+    pub const NODE_PATH: u8 = ShOptionType::NodePath as u8;
+    #[constant]
+    pub const ENUM: u8 = ShOptionType::Enum as u8;
+}
+
+#[derive(GodotClass)]
+/// Declaration of a `Command` parameter, parsed and validated by shell before `_start_with_options()`.
+#[class(init, base = Resource)]
+pub struct CommandOption {
+    #[export]
+    /// Key of the value in options Dictionary, also the long flag `--name` if it's not positional.
+    name: GString,
+
+    #[export]
+    /// Single character short flag, e.g. `f` for `-f`, empty for none.
+    short: GString,
+
+    #[export]
+    #[init(val = ShOptionType::String)]
+    option_type: ShOptionType,
+
+    #[export]
+    /// Positional options take the parameters not belonging to any flag in declaration order.
+    positional: bool,
+
+    #[export]
+    required: bool,
+
+    #[export]
+    /// Value used when the option is not given, empty for none.
+    default_value: GString,
+
+    #[export]
+    /// Valid values of the `ENUM` type option.
+    choices: Array<GString>,
+
+    #[export(multiline)]
+    description: GString,
+
+    base: Base<Resource>,
+}
+
+impl CommandOption {
+    pub fn to_spec(&self) -> OptionSpec {
+        let default = self.default_value.to_string();
+        OptionSpec {
+            name: self.name.to_string(),
+            short: self.short.to_string().chars().next(),
+            kind: self.option_type.into(),
+            positional: self.positional,
+            required: self.required,
+            default: (!default.is_empty()).then_some(default),
+            choices: self.choices.iter_shared().map(|c| c.to_string()).collect(),
+            description: self.description.to_string(),
+        }
+    }
+}
+
+/// Convert the parsed values to the Dictionary passed to `_start_with_options()`,
+/// the options not given and without default are `null`.
+pub fn options_dictionary(values: Vec<(String, Option<OptionValue>)>) -> Dictionary {
+    let mut dict = Dictionary::new();
+    for (name, value) in values {
+        let value = match value {
+            Some(OptionValue::Int(v)) => v.to_variant(),
+            Some(OptionValue::Float(v)) => v.to_variant(),
+            Some(OptionValue::Bool(v)) => v.to_variant(),
+            Some(OptionValue::String(v)) => GString::from(v.as_str()).to_variant(),
+            Some(OptionValue::NodePath(v)) => NodePath::from(v.as_str()).to_variant(),
+            None => Variant::nil(),
+        };
+        dict.set(GString::from(name.as_str()), value);
+    }
+    dict
+}
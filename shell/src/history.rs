@@ -0,0 +1,89 @@
+use godot::{
+    classes::{FileAccess, file_access::ModeFlags},
+    prelude::*,
+};
+use std::str::FromStr;
+
+/// Load the history commands from file, one command per line, from the oldest to the latest.
+///
+/// The path can be any path supported by Godot's `FileAccess`, e.g. `user://termdot_history`.
+pub fn load_history(path: &str) -> Vec<String> {
+    let path = GString::from_str(path).unwrap();
+    if !FileAccess::file_exists(&path) {
+        return vec![];
+    }
+
+    FileAccess::get_file_as_string(&path)
+        .to_string()
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(unescape)
+        .collect()
+}
+
+/// Overwrite the history file with the given commands.
+pub fn save_history(path: &str, history: &[String]) {
+    let path = GString::from_str(path).unwrap();
+    let mut file = match FileAccess::open(&path, ModeFlags::WRITE) {
+        Some(file) => file,
+        None => {
+            godot_warn!(
+                "[history::save_history] Open history file `{}` failed, err = {:?}",
+                path,
+                FileAccess::get_open_error()
+            );
+            return;
+        }
+    };
+
+    let mut content = String::new();
+    for command in history {
+        content.push_str(&escape(command));
+        content.push('\n');
+    }
+    file.store_string(&GString::from_str(&content).unwrap());
+    file.close();
+}
+
+/// Escape the backslashes and line breaks, so that every command takes exactly one line.
+fn escape(command: &str) -> String {
+    command.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(line: &str) -> String {
+    let mut command = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            command.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => command.push('\n'),
+            Some(c) => command.push(c),
+            None => command.push('\\'),
+        }
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("echo a\nb"), r"echo a\nb");
+        assert_eq!(escape(r"say \n"), r"say \\n");
+        assert_eq!(escape("a\\"), r"a\\");
+
+        for command in ["echo a\nb", r"echo a\ b", r"echo a\", "\\n\n", r"\\", ""] {
+            assert_eq!(unescape(&escape(command)), command);
+        }
+
+        assert_eq!(unescape(r"a\nb"), "a\nb");
+        assert_eq!(unescape(r"a\\nb"), r"a\nb");
+        assert_eq!(unescape(r"a\"), r"a\");
+    }
+}
@@ -1,6 +1,8 @@
 pub mod command;
 pub mod consoel_captures;
+pub mod history;
 pub mod plugin;
+pub mod script;
 pub mod session;
 pub mod shell;
 pub mod termdot;
@@ -0,0 +1,61 @@
+use godot::{classes::FileAccess, prelude::*};
+use std::str::FromStr;
+
+/// Determines if the script file exists.
+#[inline]
+pub fn script_exists(path: &str) -> bool {
+    FileAccess::file_exists(&GString::from_str(path).unwrap())
+}
+
+/// Load the command lines from the script file, see [`script_lines`].
+///
+/// The path can be any path supported by Godot's `FileAccess`, e.g. `res://scripts/setup.cfg`.
+pub fn load_script(path: &str) -> Result<Vec<(usize, String)>, String> {
+    let gpath = GString::from_str(path).unwrap();
+    if !FileAccess::file_exists(&gpath) {
+        return Err(format!("script file `{}` not found", path));
+    }
+
+    let content = FileAccess::get_file_as_string(&gpath);
+    let error = FileAccess::get_open_error();
+    if error != godot::global::Error::OK {
+        return Err(format!(
+            "read script file `{}` failed, err = {:?}",
+            path, error
+        ));
+    }
+
+    Ok(script_lines(&content.to_string()))
+}
+
+/// Split the text of script file into command lines, represent as (line number, line).
+///
+/// Lines are trimmed, blank lines and comments starting with `#` are skipped, the line numbers start from 1.
+fn script_lines(text: &str) -> Vec<(usize, String)> {
+    text.lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| (number, line.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_lines() {
+        let text =
+            "# setup\n\n  godmode on  \r\n\t# indented comment\ngive sword # not a comment\n   \n";
+        assert_eq!(
+            script_lines(text),
+            vec![
+                (3, "godmode on".to_string()),
+                (5, "give sword # not a comment".to_string()),
+            ]
+        );
+        assert_eq!(script_lines(""), vec![]);
+        assert_eq!(script_lines("#!\n#"), vec![]);
+    }
+}
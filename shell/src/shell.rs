@@ -3,20 +3,49 @@ use crate::{
         Command,
         execute_status::ShExecuteStatus,
         internal::{
-            IInternalCommand, InternalCommand, InternalCommandHnd, cls::CmdCls, log::CmdLog,
+            IInternalCommand, InternalCommand, InternalCommandHnd,
+            alias::CmdAlias,
+            cls::CmdCls,
+            echo::CmdEcho,
+            fg::CmdFg,
+            help::{CmdHelp, CommandHelp},
+            jobs::CmdJobs,
+            kill::CmdKill,
+            log::CmdLog,
+            set::CmdSet,
+            source::CmdSource,
+            unalias::CmdUnalias,
+            unset::CmdUnset,
+            vars::CmdVars,
             version::CmdVersion,
+            watch::CmdWatch,
         },
+        option::options_dictionary,
     },
+    history::{load_history, save_history},
+    script::{load_script, script_exists},
     utils::{
         ansi_string::{godot::AnsiString, rust::ShAnsiString},
         charmap::*,
+        color256::Color256,
+        highlight::{Highlight, highlight},
+        key::{KeyCode, KeyDecoder, KeyEvent, Modifiers, paste_text},
+        options::{ParsedOptions, complete_options, parse_options},
+        prompt::render_prompt,
+        tokenizer::{
+            ChainedCommand, Operator, TokenizeError, expand_aliases, is_alias_name,
+            is_variable_name, quote, split_commands, tokenize, tokenize_partial,
+        },
     },
 };
 use ahash::AHashMap;
 use common::escape_sequence::*;
 use derivative::Derivative;
 use godot::{
-    builtin::{GString, Vector2i, array},
+    builtin::{Array, Dictionary, GString, Vector2i, array},
+    classes::{Engine, SceneTree, Time},
+    global::godot_warn,
+    meta::ToGodot,
     obj::Gd,
 };
 use ipc::ipc_event::IpcEvent;
@@ -26,9 +55,33 @@ use termio::emulator::{
     emulation::{Emulation, VT102Emulation},
 };
 use tmui::tlib::{global::SemanticExt, ptr_mut};
-use wchar::{wch, wchar_t};
+use unicode_width::UnicodeWidthChar;
+use wchar::wchar_t;
 use widestring::WideString;
 
+/// Enable the bracketed paste mode of terminal, so the pasted text is surrounded by `ESC[200~` and `ESC[201~`.
+/// Only sent to terminal rather than [`emulation`](Shell::emulation).
+const BRACKETED_PASTE_ON: &str = "\x1B[?2004h";
+/// Disable the bracketed paste mode of terminal.
+const BRACKETED_PASTE_OFF: &str = "\x1B[?2004l";
+
+/// Maximum nesting depth of the scripts sourced by scripts, which stops the scripts sourcing themselves.
+const MAX_SOURCE_DEPTH: usize = 16;
+
+/// Maximum number of entries kept in the kill ring.
+const KILL_RING_SIZE: usize = 32;
+
+/// Exit code of the command line with syntax error.
+const EXIT_CODE_SYNTAX_ERROR: i32 = 2;
+/// Exit code when the command is not found.
+const EXIT_CODE_NOT_FOUND: i32 = 127;
+/// Exit code of the command interrupted by Control+C.
+const EXIT_CODE_INTERRUPTED: i32 = 130;
+/// Line breaks of the multi-line command are displayed as this symbol when the command is edited in one line.
+const NEWLINE_SYMBOL: &str = "⏎";
+/// Secondary prompt of the continuation lines.
+const CONTINUATION_PROMPT: &str = "..> \u{200B}";
+
 thread_local! {
     pub static SHELL: RefCell<Option<NonNull<Shell>>> = const { RefCell::new(None) };
 }
@@ -36,25 +89,36 @@ thread_local! {
 #[derive(Derivative)]
 #[derivative(Default)]
 pub struct Shell {
-    prompt: String,
+    host_name: String,
+    /// Template of the prompt with placeholders, the default `host_name> ` prompt is used if it's empty.
+    prompt_template: String,
     buffer: Vec<wchar_t>,
     buffer_storage: VecDeque<wchar_t>,
     cursor: usize,
     u_stack: Vec<Vec<wchar_t>>,
     d_stack: Vec<Vec<wchar_t>>,
+    /// Texts removed by Control+K/U/W and Alt+Backspace, yanked back by Control+Y.
+    kill_ring: Vec<Vec<wchar_t>>,
+    /// File to persist the history commands, `None` to keep history in memory only.
+    history_path: Option<String>,
+    #[derivative(Default(value = "500"))]
+    history_size: usize,
+    /// Active `(reverse-i-search)` triggered by Control+R.
+    history_search: Option<HistorySearch>,
+    /// Lines entered so far of the multi-line command, which is continued by a trailing `\` or unclosed quote.
+    continuation: Option<String>,
     /// (Cols, Rows)
     cursor_origin: Vector2i,
     columns: i32,
     replay_hint: bool,
-
-    /// Simple state machine to handle specific escape sequence functional behavior,
-    /// remaining sequences will be handled in [`emulation`](Shell::emulation):
-    ///
-    /// argv[0] == '\x1B'  =>  Escape Mode
-    /// argv[1] == `[`     =>  CSI Mode
-    /// argv[1] == `O`     =>  SS3 Mode
-    /// argv[2] !=  0      =>  Parameter added
-    argv: [wchar_t; 3],
+    #[derivative(Default(value = "true"))]
+    syntax_highlighting: bool,
+    /// Show the rest of the most recent matching history command after the input.
+    #[derivative(Default(value = "true"))]
+    autosuggestions: bool,
+    /// Wait for Enter before running the pasted lines.
+    #[derivative(Default(value = "true"))]
+    confirm_paste: bool,
 
     internal_command_map: AHashMap<String, InternalCommand>,
     command_map: AHashMap<String, Gd<Command>>,
@@ -64,6 +128,148 @@ pub struct Shell {
 
     running_command: Option<Gd<Command>>,
     running_internal_command: Option<InternalCommandHnd>,
+    /// Remaining commands of the command line chained by `;`, `&&` and `||`.
+    command_queue: VecDeque<ChainedCommand>,
+    /// Script lines and interrupted commands waiting for the command queue to be drained.
+    pending_lines: VecDeque<PendingLine>,
+    /// The script line whose commands are in the command queue.
+    current_script_line: Option<ScriptLine>,
+    /// Id generator of the sourced scripts.
+    script_count: usize,
+    /// Shell variables, represent as `$name` or `${name}` in command line.
+    variables: AHashMap<String, String>,
+    /// Aliases of command line, expanded on the first word of commands.
+    aliases: AHashMap<String, String>,
+    /// Exit code of the last finished command, `0` means succeeded, represent as `$?`.
+    last_exit_code: i32,
+    /// Exit code set by [`set_exit_code`](Shell::set_exit_code) for the failed command.
+    pending_exit_code: Option<i32>,
+    /// Output of the last finished command may not end with line break,
+    /// should start a new line before next output.
+    pending_crlf: bool,
+    /// Input requested by the running command, the keystrokes edit its answer instead of being stored.
+    input_request: Option<InputRequest>,
+    /// Whether the keys are decoded and delivered to the running command instead of being stored.
+    raw_input: bool,
+    /// Decoder of the keys from terminal, which feed the line editor or the running command.
+    key_decoder: KeyDecoder,
+    /// Commands running in the background, started by the command line ending with `&`.
+    jobs: Vec<Job>,
+    /// Id of the next background job, restarted from 1 once all the jobs finished.
+    next_job_id: usize,
+    /// Output of the background job or the command run by `watch`, captured rather than displayed while it's
+    /// being started or ticked.
+    captured_output: Option<String>,
+    /// Whether the command with its output captured asked for input, which is interrupted as it can't be answered.
+    input_rejected: bool,
+}
+
+/// A command running in the background, ticked with the foreground command until it finished.
+pub struct Job {
+    pub id: usize,
+    /// The command line which started the job, without the trailing `&`.
+    pub line: String,
+    pub command: JobCommand,
+}
+
+#[derive(Clone)]
+pub enum JobCommand {
+    Internal(InternalCommandHnd),
+    Script(Gd<Command>),
+}
+
+impl JobCommand {
+    #[inline]
+    pub fn running(&self) -> ShExecuteStatus {
+        match *self {
+            Self::Internal(icmd) => ptr_mut!(icmd).running(),
+            Self::Script(ref gd) => Command::running(gd.clone()),
+        }
+    }
+
+    #[inline]
+    pub fn interrupting(&self) {
+        match *self {
+            Self::Internal(icmd) => ptr_mut!(icmd).interrupting(),
+            Self::Script(ref gd) => Command::interrupting(gd.clone()),
+        }
+    }
+
+    #[inline]
+    pub fn on_key(&self, key: Dictionary) {
+        match *self {
+            Self::Internal(icmd) => ptr_mut!(icmd).on_key(key),
+            Self::Script(ref gd) => Command::on_key(gd.clone(), key),
+        }
+    }
+}
+
+impl PartialEq for JobCommand {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Internal(a), Self::Internal(b)) => std::ptr::addr_eq(*a, *b),
+            (Self::Script(a), Self::Script(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Kind of the input requested by the running [`Command`].
+pub enum InputKind {
+    /// Any line of text, answered as `String`.
+    Line,
+    /// Yes or no question, answered as `bool`.
+    Confirm,
+    /// One of the options chosen by number or text, answered as the `int` index.
+    Choose(Vec<String>),
+}
+
+struct InputRequest {
+    command: Gd<Command>,
+    kind: InputKind,
+    prompt: String,
+}
+
+/// A command line of the sourced script file.
+struct ScriptLine {
+    /// Id of the sourced script the line belongs to.
+    script: usize,
+    /// Nesting depth of the script, `1` for the script sourced by the command line.
+    depth: usize,
+    path: String,
+    number: usize,
+    line: String,
+    stop_on_failure: bool,
+}
+
+enum PendingLine {
+    Script(ScriptLine),
+    /// The remaining commands of the line which sourced a script, resumed after the script finished.
+    Resume {
+        commands: VecDeque<ChainedCommand>,
+        line: Option<ScriptLine>,
+    },
+}
+
+impl PendingLine {
+    #[inline]
+    fn script(&self) -> Option<usize> {
+        match self {
+            Self::Script(line) => Some(line.script),
+            Self::Resume { line, .. } => line.as_ref().map(|l| l.script),
+        }
+    }
+}
+
+/// State of the reverse incremental history search.
+struct HistorySearch {
+    query: Vec<wchar_t>,
+    /// Index of the matched entry in [`history`](Shell::history).
+    matched: Option<usize>,
+    /// Buffer and cursor before searching, restored when the search was canceled.
+    saved_buffer: Vec<wchar_t>,
+    saved_cursor: usize,
 }
 
 impl Shell {
@@ -96,6 +302,11 @@ impl Shell {
     #[inline]
     pub fn echo(&mut self, mut text: Gd<AnsiString>) {
         let t = text.bind().as_str().to_string();
+        if let Some(output) = self.captured_output.as_mut() {
+            output.push_str(&t);
+            text.queue_free();
+            return;
+        }
         self.echos.extend(IpcEvent::pack_data(&t));
 
         let wstr = WideString::from_str(&t);
@@ -111,6 +322,10 @@ impl Shell {
     #[inline]
     pub fn sh_echo(&mut self, text: ShAnsiString) {
         let text = text.as_str();
+        if let Some(output) = self.captured_output.as_mut() {
+            output.push_str(text);
+            return;
+        }
         self.echos.extend(IpcEvent::pack_data(text));
 
         let wstr = WideString::from_str(text);
@@ -136,6 +351,71 @@ impl Shell {
 
         let cmd = CmdLog::default().boxed();
         self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdEcho.boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdSet.boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdUnset.boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdVars.boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdAlias.boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdUnalias.boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdHelp.boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdSource::new("source").boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdSource::new("exec").boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdJobs.boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdFg::default().boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdKill.boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+
+        let cmd = CmdWatch::default().boxed();
+        self.internal_command_map.insert(cmd.command_name(), cmd);
+    }
+
+    /// Set the file to persist history commands and the maximum size of history,
+    /// then load the history commands from the file.
+    pub fn set_history_file(&mut self, path: &str, size: usize) {
+        self.history_size = size;
+        self.history_path = if path.is_empty() {
+            None
+        } else {
+            Some(path.to_string())
+        };
+
+        let path = match self.history_path.as_ref() {
+            Some(path) => path,
+            None => return,
+        };
+        let mut history = load_history(path);
+        if history.len() > size {
+            history.drain(..history.len() - size);
+        }
+
+        self.u_stack = history
+            .iter()
+            .map(|command| string_to_wide(command))
+            .collect();
+        self.d_stack.clear();
     }
 
     #[inline]
@@ -145,12 +425,299 @@ impl Shell {
 
     #[inline]
     pub fn set_prompt(&mut self, host_name: &str) {
-        self.prompt = format!("{}> \u{200B}", host_name);
+        self.host_name = host_name.to_string();
+    }
+
+    #[inline]
+    pub fn set_prompt_template(&mut self, template: &str) {
+        self.prompt_template = template.to_string();
+    }
+
+    #[inline]
+    pub fn set_syntax_highlighting(&mut self, enable: bool) {
+        self.syntax_highlighting = enable;
+    }
+
+    #[inline]
+    pub fn set_autosuggestions(&mut self, enable: bool) {
+        self.autosuggestions = enable;
+    }
+
+    #[inline]
+    pub fn set_confirm_paste(&mut self, confirm: bool) {
+        self.confirm_paste = confirm;
+    }
+
+    /// Set the exit code of the command which is going to return [`ShExecuteStatus::Failed`].
+    #[inline]
+    pub fn set_exit_code(&mut self, code: i32) {
+        self.pending_exit_code = Some(code);
+    }
+
+    /// Deliver the keys to `on_key()` of the running command while it's running,
+    /// rather than storing them for the next command line.
+    #[inline]
+    pub fn set_raw_input(&mut self, enable: bool) {
+        if enable && self.reject_input() {
+            return;
+        }
+        self.raw_input = enable;
+        self.key_decoder.reset();
+    }
+
+    /// Ask the user for input on behalf of the running command, the answer is delivered
+    /// by [`Command::deliver_input`] once Enter is pressed.
+    pub fn request_input(&mut self, command: Gd<Command>, kind: InputKind, prompt: String) {
+        if self.reject_input() {
+            return;
+        }
+        if self.get_cursor_position().x != 1 {
+            self.next_line();
+        }
+        if let InputKind::Choose(options) = &kind {
+            let mut list = ShAnsiString::new();
+            for (i, option) in options.iter().enumerate() {
+                list = list.append(&format!("  {}) {}", i + 1, option)).crlf();
+            }
+            self.sh_echo(list);
+        }
+
+        self.buffer.clear();
+        self.cursor = 0;
+        self.show_input_prompt(&prompt);
+        self.input_request = Some(InputRequest {
+            command,
+            kind,
+            prompt,
+        });
+    }
+
+    #[inline]
+    pub fn last_exit_code(&self) -> i32 {
+        self.last_exit_code
+    }
+
+    /// Set the shell variable, return false if the name is not a valid variable name.
+    #[inline]
+    pub fn set_var(&mut self, name: &str, value: &str) -> bool {
+        if !is_variable_name(name) {
+            return false;
+        }
+        self.variables.insert(name.to_string(), value.to_string());
+        true
+    }
+
+    #[inline]
+    pub fn get_var(&self, name: &str) -> Option<&String> {
+        self.variables.get(name)
+    }
+
+    /// Remove the shell variable, return false if the variable doesn't exist.
+    #[inline]
+    pub fn unset_var(&mut self, name: &str) -> bool {
+        self.variables.remove(name).is_some()
+    }
+
+    /// All the shell variables sorted by name.
+    #[inline]
+    pub fn vars(&self) -> Vec<(&String, &String)> {
+        let mut vars: Vec<(&String, &String)> = self.variables.iter().collect();
+        vars.sort();
+        vars
+    }
+
+    /// Set the alias, return false if the name is not a valid alias name.
+    #[inline]
+    pub fn set_alias(&mut self, name: &str, value: &str) -> bool {
+        if !is_alias_name(name) {
+            return false;
+        }
+        self.aliases.insert(name.to_string(), value.to_string());
+        true
+    }
+
+    #[inline]
+    pub fn get_alias(&self, name: &str) -> Option<&String> {
+        self.aliases.get(name)
+    }
+
+    /// Remove the alias, return false if the alias doesn't exist.
+    #[inline]
+    pub fn unalias(&mut self, name: &str) -> bool {
+        self.aliases.remove(name).is_some()
+    }
+
+    /// All the aliases sorted by name.
+    #[inline]
+    pub fn aliases(&self) -> Vec<(&String, &String)> {
+        let mut aliases: Vec<(&String, &String)> = self.aliases.iter().collect();
+        aliases.sort();
+        aliases
+    }
+
+    /// Help of the internal command or command, `path` is the command name followed by the subcommand names.
+    pub fn command_help(&self, path: &[String]) -> Option<CommandHelp> {
+        let (name, subcommands) = path.split_first()?;
+        if let Some(icmd) = self.internal_command_map.get(name) {
+            return subcommands
+                .is_empty()
+                .then(|| CommandHelp::from_internal(icmd.as_ref()));
+        }
+
+        let (gd, depth) = Command::resolve(self.command_map.get(name)?, subcommands);
+        (depth == subcommands.len()).then(|| CommandHelp::from_command(&path.join(" "), &gd))
+    }
+
+    /// Help of all the internal commands and commands respectively, sorted by name.
+    pub fn command_helps(&self) -> (Vec<CommandHelp>, Vec<CommandHelp>) {
+        let mut internal_helps: Vec<CommandHelp> = self
+            .internal_command_map
+            .values()
+            .map(|icmd| CommandHelp::from_internal(icmd.as_ref()))
+            .collect();
+        internal_helps.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut command_helps: Vec<CommandHelp> = self
+            .command_map
+            .iter()
+            .map(|(name, gd)| CommandHelp::from_command(name, gd))
+            .collect();
+        command_helps.sort_by(|a, b| a.name.cmp(&b.name));
+
+        (internal_helps, command_helps)
+    }
+
+    #[inline]
+    pub fn has_internal_command(&self, name: &str) -> bool {
+        self.internal_command_map.contains_key(name)
+    }
+
+    /// Id and command line of the background jobs, in the order they started.
+    #[inline]
+    pub fn jobs(&self) -> Vec<(usize, &String)> {
+        self.jobs.iter().map(|job| (job.id, &job.line)).collect()
+    }
+
+    /// Remove the background job from the job list without interrupting it, the latest one if `id` is `None`.
+    #[inline]
+    pub fn take_job(&mut self, id: Option<usize>) -> Option<Job> {
+        let idx = match id {
+            Some(id) => self.jobs.iter().position(|job| job.id == id)?,
+            None => self.jobs.len().checked_sub(1)?,
+        };
+        Some(self.jobs.remove(idx))
+    }
+
+    /// Interrupt the background job, return false if the job doesn't exist.
+    pub fn kill_job(&mut self, id: usize) -> bool {
+        let Some(job) = self.take_job(Some(id)) else {
+            return false;
+        };
+
+        let ((), mut output) = self.capture(|_| job.command.interrupting());
+        output.push_str(&format!("Terminated  {}", job.line));
+        self.output(&tag_lines(job.id, &output));
+        true
+    }
+
+    /// Start the command with its output captured rather than displayed, for the commands running other
+    /// commands like `watch`. The command is returned if it's still running, which should be ticked by
+    /// [`tick_captured`](Shell::tick_captured) instead of shell. The exit code doesn't change `$?`.
+    pub fn start_captured(&mut self, words: Vec<String>) -> (Option<JobCommand>, String) {
+        let ((_, running), output) = self.capture(|shell| shell.start_without_input(words));
+        self.pending_exit_code = None;
+        self.pending_crlf = false;
+        (running, output)
+    }
+
+    /// Parse the line of a single command like a typed command line, with the aliases and variables expanded,
+    /// for the commands running other commands like `watch`. Chained and background commands are rejected.
+    pub fn parse_single_command(&self, line: &str) -> Result<Vec<String>, String> {
+        let mut commands = self.parse_line(line).map_err(|e| e.to_string())?;
+        match commands.pop() {
+            None => Err("missing command".to_string()),
+            Some(command) if commands.is_empty() && !command.background => Ok(command.words),
+            Some(_) => Err("`;`, `&&`, `||` and `&` are not supported".to_string()),
+        }
+    }
+
+    /// Tick the command returned by [`start_captured`](Shell::start_captured) with its output captured,
+    /// return false as well if it's finished.
+    pub fn tick_captured(&mut self, command: &JobCommand) -> (bool, String) {
+        let (status, output) = self.capture(|shell| shell.tick_without_input(command));
+        if status != ShExecuteStatus::Running {
+            self.pending_exit_code = None;
+        }
+        (status == ShExecuteStatus::Running, output)
+    }
+
+    /// Interrupt the command returned by [`start_captured`](Shell::start_captured), the output is dropped.
+    pub fn interrupt_captured(&mut self, command: &JobCommand) {
+        self.capture(|_| command.interrupting());
+    }
+
+    /// Queue the lines of the script file, which are executed one by one after the current command finished,
+    /// the remaining commands of the current line are resumed after the script.
+    ///
+    /// If `stop_on_failure` is true, the remaining lines are skipped once a line failed.
+    ///
+    /// The script runs in the foreground, so it can't be started as a background job or by `watch`.
+    pub fn source(&mut self, path: &str, stop_on_failure: bool) -> Result<(), String> {
+        if self.captured_output.is_some() {
+            return Err("scripts can't run in the background".to_string());
+        }
+        let depth = self
+            .current_script_line
+            .as_ref()
+            .map_or(0, |line| line.depth)
+            + 1;
+        if depth > MAX_SOURCE_DEPTH {
+            return Err(format!(
+                "recursion limit {} exceeded by `{}`",
+                MAX_SOURCE_DEPTH, path
+            ));
+        }
+        let lines = load_script(path)?;
+
+        if !self.command_queue.is_empty() || self.current_script_line.is_some() {
+            self.pending_lines.push_front(PendingLine::Resume {
+                commands: std::mem::take(&mut self.command_queue),
+                line: self.current_script_line.take(),
+            });
+        }
+
+        self.script_count += 1;
+        for (number, line) in lines.into_iter().rev() {
+            self.pending_lines
+                .push_front(PendingLine::Script(ScriptLine {
+                    script: self.script_count,
+                    depth,
+                    path: path.to_string(),
+                    number,
+                    line,
+                    stop_on_failure,
+                }));
+        }
+        Ok(())
+    }
+
+    /// Run the autoexec script file when the session started, prompt directly if the file doesn't exist.
+    pub fn autoexec(&mut self, path: &str) {
+        if !path.is_empty() && script_exists(path) {
+            match self.source(path, false) {
+                Ok(()) => {
+                    self.run_command_queue();
+                    return;
+                }
+                Err(e) => godot_warn!("[Shell::autoexec] Run autoexec failed, {}.", e),
+            }
+        }
+        self.prompt();
     }
 
     #[inline]
     pub fn prompt(&mut self) {
-        let prompt = format!("{}{}", ESC0M, self.prompt);
+        let prompt = format!("{}{}", ESC0M, self.prompt_text());
         let wstr = WideString::from_str(&prompt);
         for &c in wstr.as_slice() {
             #[allow(clippy::useless_transmute)]
@@ -164,7 +731,7 @@ impl Shell {
 
     #[inline]
     pub fn crlf_prompt(&mut self) {
-        let prompt = format!("{}\r\n{}", ESC0M, self.prompt);
+        let prompt = format!("{}\r\n{}", ESC0M, self.prompt_text());
         let wstr = WideString::from_str(&prompt);
         for &c in wstr.as_slice() {
             #[allow(clippy::useless_transmute)]
@@ -176,6 +743,18 @@ impl Shell {
         self.columns = self.get_terminal_size().x;
     }
 
+    /// Enable the bracketed paste mode of terminal once the session started,
+    /// and disable it before the session exits.
+    #[inline]
+    pub fn set_bracketed_paste(&mut self, enable: bool) {
+        let mode = if enable {
+            BRACKETED_PASTE_ON
+        } else {
+            BRACKETED_PASTE_OFF
+        };
+        self.echos.extend(IpcEvent::pack_data(mode));
+    }
+
     #[inline]
     pub fn next_echo(&mut self) -> Option<IpcEvent> {
         self.echos.pop_front()
@@ -184,16 +763,35 @@ impl Shell {
     #[inline]
     pub fn process_running_command(&mut self) {
         if let Some(icmd) = self.running_internal_command {
-            if ptr_mut!(icmd).running() == ShExecuteStatus::Done {
+            let status = ptr_mut!(icmd).running();
+            if status != ShExecuteStatus::Running {
                 self.running_internal_command = None;
-                self.crlf_prompt();
+                self.finish_running_command(status);
             }
         }
 
         if let Some(gd) = self.running_command.clone() {
-            if Command::running(gd) == ShExecuteStatus::Done {
+            let status = Command::running(gd);
+            if status != ShExecuteStatus::Running {
                 self.running_command = None;
-                self.crlf_prompt();
+                self.finish_running_command(status);
+            }
+        }
+
+        let mut i = 0;
+        while i < self.jobs.len() {
+            let (id, command) = (self.jobs[i].id, self.jobs[i].command.clone());
+
+            let (status, mut output) = self.capture(|shell| shell.tick_without_input(&command));
+
+            if status == ShExecuteStatus::Running {
+                i += 1;
+            } else {
+                let job = self.jobs.remove(i);
+                output.push_str(&format!("{}  {}", self.job_status(status), job.line));
+            }
+            if !output.is_empty() {
+                self.echo_above_prompt(&tag_lines(id, &output));
             }
         }
     }
@@ -203,6 +801,19 @@ impl Shell {
         self.emulation.reset();
         self.running_internal_command = None;
         self.running_command = None;
+        self.command_queue.clear();
+        self.pending_lines.clear();
+        self.current_script_line = None;
+        self.continuation = None;
+        self.history_search = None;
+        self.input_request = None;
+        self.raw_input = false;
+        // The session may be closed in the middle of an escape sequence or a bracketed paste.
+        self.key_decoder.reset();
+
+        // The output of the interrupted jobs is dropped with the session.
+        let jobs = std::mem::take(&mut self.jobs);
+        self.capture(|_| jobs.iter().for_each(|job| job.command.interrupting()));
     }
 
     #[inline]
@@ -227,6 +838,8 @@ impl Shell {
 
     #[inline]
     pub fn interrupt(&mut self, echo: bool) {
+        self.input_request = None;
+        self.raw_input = false;
         let mut interrupted = false;
         if let Some(running_command) = self.running_command.clone() {
             Command::interrupting(running_command);
@@ -242,6 +855,15 @@ impl Shell {
             interrupted = true;
         }
 
+        if interrupted {
+            self.last_exit_code = EXIT_CODE_INTERRUPTED;
+        }
+        self.pending_exit_code = None;
+        self.command_queue.clear();
+        self.pending_lines.clear();
+        self.current_script_line = None;
+        self.pending_crlf = false;
+
         if interrupted && echo {
             self.sh_echo(ShAnsiString::new().append("\r\n^C"));
         }
@@ -275,310 +897,817 @@ impl Shell {
         self.echo_replay_text();
     }
 
+    /// Should be called after a whole chunk of input was received,
+    /// an escape without following characters represents a single Esc key press.
+    #[inline]
+    pub fn flush_escape(&mut self) {
+        if let Some(key) = self.key_decoder.flush() {
+            self.receive_key(key);
+        }
+    }
+
     pub fn receive_char(&mut self, c: wchar_t) {
-        if self.is_executing() && c != CTL_SIGINT {
+        if self.is_executing() && self.input_request.is_none() && !self.raw_input && c != CTL_SIGINT
+        {
             self.buffer_storage.push_back(c);
 
             return;
         }
 
-        let oc = match c {
-            CTL_ESCAPE => {
-                self.reset_argv();
-                self.argv[0] = CTL_ESCAPE;
-                self.replay_hint = false;
-                Some(c)
+        if let Some(key) = self.key_decoder.feed(c as u32) {
+            self.receive_key(key);
+        }
+    }
+}
+
+/// Private functions:
+impl Shell {
+    fn receive_key(&mut self, key: KeyEvent) {
+        if self.history_search.is_some() && self.search_receive_key(&key) {
+            return;
+        }
+
+        if key == ctrl_key('c') {
+            self.erase_suggestion();
+            if self.continuation.take().is_some() {
+                self.buffer.clear();
+                self.cursor = 0;
+                self.output("^C");
+                self.crlf_prompt();
+            } else {
+                self.interrupt(true);
             }
-            ASCII_LEFT_SQUARE_BRACKET => {
-                if self.argv[0] == CTL_ESCAPE {
-                    self.argv[1] = ASCII_LEFT_SQUARE_BRACKET;
-                } else {
-                    self.extend(c);
+            return;
+        }
+
+        if self.input_request.is_some() {
+            self.input_receive_key(key);
+            return;
+        }
+        if self.is_executing() {
+            self.deliver_key(key);
+            return;
+        }
+
+        if let Some(c) = key.text().filter(|&c| is_printable(c)) {
+            self.extend(c);
+            return;
+        }
+        // The deferred display of the replayed characters, see [`check_buffer_storage`](Shell::check_buffer_storage).
+        self.echo_replay_text();
+
+        let KeyEvent { code, modifiers } = key;
+        // Control or Alt with the arrow keys moves by words.
+        let word = modifiers.ctrl || modifiers.alt;
+        match code {
+            KeyCode::Paste(text) => self.paste(&text),
+            KeyCode::Enter => self.submit_line(),
+            KeyCode::Tab if !modifiers.shift => self.command_completion(),
+            KeyCode::Backspace if modifiers.alt => {
+                self.kill(self.prev_word_boundary(is_word_char), self.cursor)
+            }
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete if word => {
+                self.kill(self.cursor, self.next_word_boundary(is_word_char))
+            }
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left if word => self.set_cursor(self.prev_word_boundary(is_word_char)),
+            KeyCode::Left => {
+                if self.cursor != 0 {
+                    self.set_cursor(self.prev_char_boundary(self.cursor));
                 }
-                Some(c)
-            }
-            KEY_HOME => {
-                if self.argv[1] == ASCII_LEFT_SQUARE_BRACKET {
-                    self.cursor = 0;
-                    self.emulation.receive_char(wch!(';'));
-                    self.map_set_cursor();
-                    self.report_cursor();
-                    None
-                } else {
-                    self.extend(c);
-                    Some(c)
+            }
+            KeyCode::Right if word => {
+                if !self.accept_suggestion(true) {
+                    self.set_cursor(self.next_word_boundary(is_word_char));
                 }
             }
-            KEY_END => {
-                if self.argv[1] == ASCII_LEFT_SQUARE_BRACKET {
-                    self.cursor = self.buffer.len();
-                    self.emulation.receive_char(wch!(';'));
-                    self.map_set_cursor();
-                    self.report_cursor();
-                    None
+            KeyCode::Right => {
+                if self.cursor < self.buffer.len() {
+                    self.set_cursor(self.next_char_boundary(self.cursor));
                 } else {
-                    self.extend(c);
-                    Some(c)
+                    self.accept_suggestion(false);
                 }
             }
-            KEY_LEFT => {
-                if self.argv[1] == ASCII_LEFT_SQUARE_BRACKET {
-                    if self.cursor != 0 {
-                        self.cursor -= 1;
-                        self.report_cursor();
-                        Some(c)
-                    } else {
-                        self.emulation.receive_char(wch!(';'));
-                        self.map_set_cursor();
-                        None
-                    }
-                } else {
-                    self.extend(c);
-                    Some(c)
+            KeyCode::Home => self.set_cursor(0),
+            KeyCode::End => {
+                if !self.accept_suggestion(false) {
+                    self.set_cursor(self.buffer.len());
                 }
             }
-            KEY_RIGHT => {
-                if self.argv[1] == ASCII_LEFT_SQUARE_BRACKET {
-                    if self.cursor < self.buffer.len() {
-                        self.cursor += 1;
-                        self.report_cursor();
-                        Some(c)
-                    } else {
-                        self.emulation.receive_char(wch!(';'));
-                        self.map_set_cursor();
-                        None
-                    }
-                } else {
-                    self.extend(c);
-                    Some(c)
+            KeyCode::Up => {
+                if self.history_prev() {
+                    self.redraw();
                 }
             }
-            KEY_UP => {
-                if self.argv[1] == ASCII_LEFT_SQUARE_BRACKET {
-                    if let Some(u_pop) = self.u_stack.pop() {
-                        if !self.buffer.is_empty() {
-                            self.d_stack.push(self.buffer.clone());
-                        }
-                        self.buffer = u_pop;
-                        self.cursor = self.buffer.len();
-
-                        let replay_text = self.replay_text();
-                        self.echos
-                            .extend(IpcEvent::pack_data(&replay_text.to_string_lossy()));
-
-                        for &c in replay_text.as_slice() {
-                            #[allow(clippy::useless_transmute)]
-                            let c: wchar_t = unsafe { std::mem::transmute(c) };
-                            self.emulation.receive_char(c);
-                        }
-                    }
-                    self.emulation.receive_char(wch!(';'));
-                    self.map_set_cursor();
-                    None
-                } else {
-                    self.extend(c);
-                    Some(c)
+            KeyCode::Down => {
+                if self.history_next() {
+                    self.redraw();
                 }
             }
-            KEY_DOWN => {
-                if self.argv[1] == ASCII_LEFT_SQUARE_BRACKET {
-                    if let Some(d_pop) = self.d_stack.pop() {
-                        if !self.buffer.is_empty() {
-                            self.u_stack.push(self.buffer.clone());
-                        }
-                        self.buffer = d_pop;
-                        self.cursor = self.buffer.len();
-
-                        let replay_text = self.replay_text();
-                        self.echos
-                            .extend(IpcEvent::pack_data(&replay_text.to_string_lossy()));
-
-                        for &c in replay_text.as_slice() {
-                            #[allow(clippy::useless_transmute)]
-                            let c: wchar_t = unsafe { std::mem::transmute(c) };
-                            self.emulation.receive_char(c);
-                        }
-                    }
-                    self.emulation.receive_char(wch!(';'));
-                    self.map_set_cursor();
-                    None
-                } else {
-                    self.extend(c);
-                    Some(c)
+            KeyCode::PageUp => {
+                let mut moved = false;
+                while self.history_prev() {
+                    moved = true;
+                }
+                if moved {
+                    self.redraw();
                 }
             }
-            CTL_BACKSPACE => {
-                if self.cursor != 0 {
-                    self.cursor -= 1;
-                    self.buffer.remove(self.cursor);
-
-                    let (row, col) = self.cursor_to_position();
-                    let echo = format!("\x1B[{};{}H\x1B[K", row, col);
-                    self.echos.extend(IpcEvent::pack_data(&echo));
-
-                    Some(c)
-                } else {
-                    None
+            KeyCode::PageDown => {
+                let mut moved = false;
+                while self.history_next() {
+                    moved = true;
+                }
+                if moved {
+                    self.redraw();
                 }
             }
-            CTL_TAB => {
-                self.command_completion();
-                None
+            KeyCode::Char(c) if modifiers.ctrl && !modifiers.alt => match c {
+                'a' => self.set_cursor(0),
+                'd' => {
+                    if self.buffer.is_empty() && self.continuation.is_none() {
+                        self.request_exit();
+                    } else {
+                        self.delete();
+                    }
+                }
+                'e' => {
+                    if !self.accept_suggestion(false) {
+                        self.set_cursor(self.buffer.len());
+                    }
+                }
+                'k' => self.kill(self.cursor, self.buffer.len()),
+                'l' => self.clear_screen(),
+                'u' => self.kill(0, self.cursor),
+                'w' => self.kill(self.prev_word_boundary(is_non_blank), self.cursor),
+                'y' => self.yank(),
+                'r' => self.start_search(),
+                _ => {}
+            },
+            KeyCode::Char(c) if modifiers.alt && !modifiers.ctrl => match c {
+                'b' => self.set_cursor(self.prev_word_boundary(is_word_char)),
+                'f' => {
+                    if !self.accept_suggestion(true) {
+                        self.set_cursor(self.next_word_boundary(is_word_char));
+                    }
+                }
+                'd' => self.kill(self.cursor, self.next_word_boundary(is_word_char)),
+                _ => {}
+            },
+            // Insert, function keys and the other combinations are not bound.
+            _ => {}
+        }
+    }
+
+    /// Delete the character under the cursor.
+    fn delete(&mut self) {
+        if self.cursor < self.buffer.len() {
+            let end = self.next_char_boundary(self.cursor);
+            self.buffer.drain(self.cursor..end);
+            self.redraw();
+        }
+    }
+
+    /// Clear the entire screen like the internal command `cls`, then redraw the prompt and the
+    /// current buffer at the top.
+    fn clear_screen(&mut self) {
+        self.sh_echo(CmdCls::clear_screen());
+        self.prompt();
+        self.redraw();
+    }
+
+    /// End of input on an empty line, ask the terminal to close the session.
+    ///
+    /// The terminal answers with [`IpcEvent::Exit`], which resets the shell in `Termdot`.
+    fn request_exit(&mut self) {
+        self.output("exit\r\n");
+        self.set_bracketed_paste(false);
+        self.echos.push_back(IpcEvent::RequestExit);
+    }
+
+    /// Insert the bracketed paste at cursor as one unit. The line breaks are kept, so the pasted lines run
+    /// as separate commands when Enter is pressed, or immediately if [`confirm_paste`](Shell::confirm_paste) is off.
+    fn paste(&mut self, text: &str) {
+        let text = paste_text(text);
+        if text.is_empty() {
+            return;
+        }
+
+        let wstr = string_to_wide(&text);
+        let len = wstr.len();
+        self.buffer.splice(self.cursor..self.cursor, wstr);
+        self.cursor += len;
+        self.redraw();
+
+        if text.contains('\n') && !self.confirm_paste {
+            self.submit_line();
+        }
+    }
+
+    /// Finish the input line by Enter, execute it or continue on a new line if it's incomplete.
+    fn submit_line(&mut self) {
+        self.erase_suggestion();
+
+        let mut data = self.continuation.take().unwrap_or_default();
+        data.push_str(&wide_to_string(&self.buffer));
+        self.buffer.clear();
+        self.cursor = 0;
+
+        // Continue the input on a new line if the command is incomplete.
+        if matches!(
+            tokenize(&data),
+            Err(TokenizeError::TrailingBackslash | TokenizeError::UnclosedQuote(_))
+        ) {
+            data.push('\n');
+            self.continuation = Some(data);
+            self.crlf_prompt();
+        } else {
+            self.add_history(string_to_wide(&data));
+            self.execute_command(&data);
+        }
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+
+        if self.cursor == self.buffer.len() && !self.syntax_highlighting && !self.autosuggestions {
+            self.cursor = self.prev_char_boundary(self.cursor);
+            self.buffer.truncate(self.cursor);
+
+            let (row, col) = self.cursor_to_position();
+            self.output(&format!("\x1B[{};{}H\x1B[K", row, col));
+        } else {
+            let end = self.cursor;
+            self.cursor = self.prev_char_boundary(end);
+            self.buffer.drain(self.cursor..end);
+            self.redraw();
+        }
+    }
+
+    /// Replace the buffer with the previous history command, return false if there's no more.
+    fn history_prev(&mut self) -> bool {
+        let Some(u_pop) = self.u_stack.pop() else {
+            return false;
+        };
+        if !self.buffer.is_empty() {
+            self.d_stack.push(std::mem::take(&mut self.buffer));
+        }
+        self.buffer = u_pop;
+        self.cursor = self.buffer.len();
+        true
+    }
+
+    /// Replace the buffer with the next history command, return false if there's no more.
+    fn history_next(&mut self) -> bool {
+        let Some(d_pop) = self.d_stack.pop() else {
+            return false;
+        };
+        if !self.buffer.is_empty() {
+            self.u_stack.push(std::mem::take(&mut self.buffer));
+        }
+        self.buffer = d_pop;
+        self.cursor = self.buffer.len();
+        true
+    }
+
+    #[inline]
+    fn extend(&mut self, c: char) {
+        let wstr = string_to_wide(c.encode_utf8(&mut [0; 4]));
+        let len = wstr.len();
+        self.buffer
+            .splice(self.cursor..self.cursor, wstr.iter().copied());
+        self.cursor += len;
+
+        // The line is displayed once after all the characters replayed.
+        if self.replay_hint {
+            for c in wstr {
+                self.emulation.receive_char(c);
             }
-            CTL_SIGINT => {
-                self.interrupt(true);
-                None
+            return;
+        }
+
+        // Highlighting and suggestion of the whole line may change by the typed character,
+        // so the line is always redrawn if they're enabled.
+        if self.cursor == self.buffer.len() && !self.syntax_highlighting && !self.autosuggestions {
+            self.output(&c.to_string());
+        } else {
+            self.redraw();
+        }
+    }
+
+    fn execute_command(&mut self, data: &str) {
+        let commands = match self.parse_line(data) {
+            Ok(commands) => commands,
+            Err(e) => {
+                self.last_exit_code = EXIT_CODE_SYNTAX_ERROR;
+                self.next_line();
+                self.sh_echo(
+                    ShAnsiString::new()
+                        .foreground_256(Color256::RED)
+                        .append(&format!("Syntax error: {}.", e))
+                        .clear_style()
+                        .crlf(),
+                );
+                self.prompt();
+                return;
             }
-            CTL_NEWLINE => None,
-            CTL_CARRIAGE_RETURN => {
-                self.echo_replay_text();
+        };
+
+        self.next_line();
+        self.command_queue.extend(commands);
+        self.run_command_queue();
+    }
+
+    /// Expand the aliases and variables of the command line, and split it into chained commands.
+    fn parse_line(&self, data: &str) -> Result<Vec<ChainedCommand>, TokenizeError> {
+        let data = expand_aliases(data, &|name| self.aliases.get(name).cloned());
+
+        let last_exit_code = self.last_exit_code;
+        // Undefined variables are expanded to empty string.
+        let expand = |name: &str| match name {
+            "?" => Some(last_exit_code.to_string()),
+            _ => Some(self.variables.get(name).cloned().unwrap_or_default()),
+        };
+        split_commands(&data, &expand)
+    }
 
-                #[allow(clippy::useless_transmute)]
-                let buffer: Vec<uwchar_t> = unsafe { std::mem::transmute(self.buffer.clone()) };
-                let data = WideString::from_vec(buffer).to_string_lossy();
-                if !self.buffer.is_empty() {
-                    self.u_stack.push(self.buffer.clone());
+    /// Execute the queued commands one by one, until one of them is running
+    /// or all of them and the pending lines are executed.
+    fn run_command_queue(&mut self) {
+        loop {
+            while let Some(command) = self.command_queue.pop_front() {
+                let run = match command.connector {
+                    Operator::Sequence | Operator::Background => true,
+                    Operator::And => self.last_exit_code == 0,
+                    Operator::Or => self.last_exit_code != 0,
+                };
+                if !run {
+                    continue;
                 }
-                self.buffer.clear();
-                self.cursor = 0;
 
-                self.execute_command(&data);
+                if std::mem::take(&mut self.pending_crlf) {
+                    self.next_line();
+                }
 
-                None
-            }
-            _ => {
-                if self.argv[0] == CTL_ESCAPE {
-                    // SS3 escape sequences are represent by `\x1BO*`, just ignore them for now.
-                    if self.argv[1] == CTL_SS3 {
-                        None
-                    } else if c == CTL_SS3 && self.argv[1] == 0 {
-                        self.argv[1] = CTL_SS3;
-                        None
-                    } else {
-                        Some(c)
+                if command.background {
+                    self.start_job(command.words);
+                    continue;
+                }
+                let (status, running) = self.start_command(command.words);
+                if let Some(running) = running {
+                    match running {
+                        JobCommand::Internal(icmd) => self.running_internal_command = Some(icmd),
+                        JobCommand::Script(gd) => self.running_command = Some(gd),
                     }
-                } else if self.argv[0] != CTL_ESCAPE && is_printable(c) {
-                    self.extend(c);
-                    Some(c)
-                } else {
-                    Some(c)
+                    return;
                 }
+                self.update_exit_code(status);
             }
-        };
 
-        if let Some(c) = oc {
-            self.emulation.receive_char(c);
+            if !self.next_pending_line() {
+                break;
+            }
         }
 
-        if self.argv[1] != 0 && c != ASCII_LEFT_SQUARE_BRACKET && c != CTL_SS3 {
-            self.argv[2] = c;
+        if std::mem::take(&mut self.pending_crlf) {
+            self.crlf_prompt();
+        } else {
+            self.prompt();
         }
+    }
 
-        // The remaining CSI and SS3 escape sequences are not specially handled for now;
-        // support will be added later as needed.
-        if self.argv[0] == CTL_ESCAPE && self.argv[1] != 0 {
-            if self.argv[1] == ASCII_LEFT_SQUARE_BRACKET {
-                // CSI Mode
-                if self.argv[2] != 0 && is_csi_final_byte(c) {
-                    self.reset_argv();
-                }
-            } else if self.argv[1] == CTL_SS3 {
-                // SS3 (Single Shift 3) Mode
-                if self.argv[2] != 0 && is_csi_final_byte(c) {
-                    self.reset_argv();
+    /// Move the next pending line into the command queue, return false if there is no pending line.
+    fn next_pending_line(&mut self) -> bool {
+        if let Some(line) = self.current_script_line.take() {
+            if line.stop_on_failure && self.last_exit_code != 0 {
+                self.pending_lines
+                    .retain(|pending| pending.script() != Some(line.script));
+
+                if std::mem::take(&mut self.pending_crlf) {
+                    self.next_line();
                 }
+                self.sh_echo(
+                    ShAnsiString::new()
+                        .foreground_256(Color256::RED)
+                        .append(&format!(
+                            "Script `{}` stopped at line {}.",
+                            line.path, line.number
+                        ))
+                        .clear_style()
+                        .crlf(),
+                );
             }
         }
-    }
-}
 
-/// Private functions:
-impl Shell {
-    #[inline]
-    fn extend(&mut self, c: wchar_t) {
-        self.buffer.insert(self.cursor, c);
-        self.cursor += 1;
+        let line = match self.pending_lines.pop_front() {
+            Some(PendingLine::Script(line)) => line,
+            Some(PendingLine::Resume { commands, line }) => {
+                self.command_queue = commands;
+                self.current_script_line = line;
+                return true;
+            }
+            None => return false,
+        };
 
-        if self.replay_hint {
-            return;
+        if std::mem::take(&mut self.pending_crlf) {
+            self.next_line();
+        }
+        self.output(&format!("{}{}\r\n", self.prompt_text(), line.line));
+
+        match self.parse_line(&line.line) {
+            Ok(commands) => self.command_queue.extend(commands),
+            Err(e) => {
+                self.last_exit_code = EXIT_CODE_SYNTAX_ERROR;
+                self.sh_echo(
+                    ShAnsiString::new()
+                        .foreground_256(Color256::RED)
+                        .append(&format!("Syntax error: {}.", e))
+                        .clear_style()
+                        .crlf(),
+                );
+            }
         }
+        self.current_script_line = Some(line);
+        true
+    }
 
-        if self.cursor == self.buffer.len() {
-            #[allow(clippy::useless_transmute)]
-            let c: uwchar_t = unsafe { std::mem::transmute(c) };
-            let c = WideString::from_vec(vec![c]).to_string_lossy();
-            self.echos.extend(IpcEvent::pack_data(&c));
+    /// Start the command, the first word is the command name and the rest are parameters.
+    ///
+    /// The command is returned if it's still running, the caller decides where it's ticked.
+    fn start_command(&mut self, mut words: Vec<String>) -> (ShExecuteStatus, Option<JobCommand>) {
+        if words.is_empty() {
+            return (ShExecuteStatus::Done, None);
+        }
+        let command = words.remove(0);
+        let command = command.as_str();
+
+        let (status, running) = if let Some(icmd) = self.internal_command_map.get_mut(command) {
+            let icmd: InternalCommandHnd = icmd.as_mut();
+            if !self.check_not_running(command, &JobCommand::Internal(icmd)) {
+                return (ShExecuteStatus::Failed, None);
+            }
+
+            let status = ptr_mut!(icmd).start(to_params(&words));
+            (status, JobCommand::Internal(icmd))
+        } else if let Some(gd) = self.command_map.get(command) {
+            let (gd, depth) = Command::resolve(gd, &words);
+            if !self.check_not_running(command, &JobCommand::Script(gd.clone())) {
+                return (ShExecuteStatus::Failed, None);
+            }
+            let path: Vec<String> = std::iter::once(command.to_string())
+                .chain(words.drain(..depth))
+                .collect();
+
+            let status = self.start_script_command(&path.join(" "), &gd, words);
+            (status, JobCommand::Script(gd))
         } else {
-            #[allow(clippy::useless_transmute)]
-            let buffer: Vec<uwchar_t> = unsafe { std::mem::transmute(self.buffer.clone()) };
-            let data = WideString::from_vec(buffer).to_string_lossy();
-            let (row, col) = self.cursor_to_position();
-            let echo = format!(
-                "\x1B[{};{}H\x1B[K{}\x1B[{};{}H",
-                self.cursor_origin.y, self.cursor_origin.x, data, row, col
+            let send_back = format!(
+                "`{}` is not recognized as an internal or external command.",
+                command
             );
-            self.echos.extend(IpcEvent::pack_data(&echo));
+            self.sh_echo(ShAnsiString::new().append(&send_back).crlf());
+
+            self.set_exit_code(EXIT_CODE_NOT_FOUND);
+            return (ShExecuteStatus::Failed, None);
+        };
+
+        (
+            status,
+            (status == ShExecuteStatus::Running).then_some(running),
+        )
+    }
+
+    /// Start the command in the background, the output is tagged with the job id.
+    /// The exit code of the background command is reported when it finished rather than set to `$?`.
+    fn start_job(&mut self, words: Vec<String>) {
+        if self.jobs.is_empty() {
+            self.next_job_id = 1;
+        }
+        let id = self.next_job_id;
+        self.next_job_id += 1;
+        let line = words
+            .iter()
+            .map(|word| quote(word))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let ((status, running), mut output) =
+            self.capture(|shell| shell.start_without_input(words));
+        // The output is already ended with a line break by `tag_lines`.
+        self.pending_crlf = false;
+
+        if let Some(command) = running {
+            self.jobs.push(Job {
+                id,
+                line: line.clone(),
+                command,
+            });
         }
+        output.push_str(&format!("{}  {}", self.job_status(status), line));
+        self.output(&tag_lines(id, &output));
+        self.last_exit_code = 0;
     }
 
-    fn execute_command(&mut self, data: &str) {
-        let commands = data.trim().split(" ");
-        let (mut command, mut params) = (None, array![]);
-        for (i, c) in commands.into_iter().enumerate() {
-            if i == 0 {
-                command = Some(c);
-            } else {
-                params.push(&GString::from_str(c).unwrap());
+    /// Status of the job shown in the job list and the message when it finished, e.g. `Done` or `Exit 2`.
+    fn job_status(&mut self, status: ShExecuteStatus) -> String {
+        let exit_code = self.pending_exit_code.take();
+        match status {
+            ShExecuteStatus::Running => "Running".to_string(),
+            ShExecuteStatus::Failed => {
+                format!("Exit {}", exit_code.filter(|&code| code != 0).unwrap_or(1))
             }
+            _ => "Done".to_string(),
         }
+    }
 
-        let command = match command {
-            Some(c) => c,
-            None => return,
+    /// Id of the background job running the command.
+    #[inline]
+    fn job_of(&self, command: &JobCommand) -> Option<usize> {
+        self.jobs
+            .iter()
+            .find(|job| job.command == *command)
+            .map(|job| job.id)
+    }
+
+    /// The same command can't run twice at the same time, show the error and return false
+    /// if the command is running in the foreground or as a background job.
+    fn check_not_running(&mut self, name: &str, command: &JobCommand) -> bool {
+        let foreground = match *command {
+            JobCommand::Internal(icmd) => self
+                .running_internal_command
+                .is_some_and(|running| std::ptr::addr_eq(running, icmd)),
+            JobCommand::Script(ref gd) => self.running_command.as_ref() == Some(gd),
+        };
+        let error = if foreground {
+            format!("`{}` is already running.", name)
+        } else if let Some(id) = self.job_of(command) {
+            format!("`{}` is already running as job [{}].", name, id)
+        } else {
+            return true;
         };
 
-        self.next_line();
-        if let Some(icmd) = self.internal_command_map.get_mut(command) {
-            match icmd.start(params) {
-                ShExecuteStatus::Done => self.prompt(),
-                ShExecuteStatus::Running => self.running_internal_command = Some(icmd.as_mut()),
-            }
-        } else if let Some(gd) = self.command_map.get(command) {
-            let gd = gd.clone();
+        self.sh_echo(
+            ShAnsiString::new()
+                .foreground_256(Color256::RED)
+                .append(&error)
+                .clear_style()
+                .crlf(),
+        );
+        false
+    }
+
+    /// Run `f` with the echoed output captured rather than displayed, return its result and the output.
+    fn capture<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> (R, String) {
+        let outer = self.captured_output.replace(String::new());
+        let result = f(self);
+        let output = std::mem::replace(&mut self.captured_output, outer).unwrap_or_default();
+        (result, output)
+    }
+
+    /// The command with its output captured can't read input, as the keystrokes belong to the foreground.
+    /// Show the error and return true if the output is being captured.
+    fn reject_input(&mut self) -> bool {
+        if self.captured_output.is_none() {
+            return false;
+        }
+        self.input_rejected = true;
+        self.sh_echo(
+            ShAnsiString::new()
+                .foreground_256(Color256::RED)
+                .append("Background commands can't read input, interrupted.")
+                .clear_style()
+                .crlf(),
+        );
+        true
+    }
+
+    /// Start the command with its output captured, it's interrupted and failed if it asked for input.
+    fn start_without_input(&mut self, words: Vec<String>) -> (ShExecuteStatus, Option<JobCommand>) {
+        let (status, running) = self.start_command(words);
+        if !std::mem::take(&mut self.input_rejected) {
+            return (status, running);
+        }
+        if let Some(command) = running {
+            command.interrupting();
+        }
+        (ShExecuteStatus::Failed, None)
+    }
+
+    /// Tick the command with its output captured, it's interrupted and failed if it asked for input.
+    fn tick_without_input(&mut self, command: &JobCommand) -> ShExecuteStatus {
+        let status = command.running();
+        if !std::mem::take(&mut self.input_rejected) {
+            return status;
+        }
+        if status == ShExecuteStatus::Running {
+            command.interrupting();
+        }
+        ShExecuteStatus::Failed
+    }
 
-            match Command::start(gd.clone(), params) {
-                ShExecuteStatus::Done => self.crlf_prompt(),
-                ShExecuteStatus::Running => self.running_command = Some(gd),
+    /// Print the output of the background job without breaking the input line:
+    /// the prompt and input are erased and displayed again after the output,
+    /// or the output follows the output of the running foreground command.
+    fn echo_above_prompt(&mut self, text: &str) {
+        if self.is_executing() && self.input_request.is_none() {
+            if self.get_cursor_position().x != 1 {
+                self.next_line();
             }
+            self.output(text);
+            return;
+        }
+
+        self.output(&format!("\x1B[{};1H\x1B[J", self.cursor_origin.y));
+        self.output(text);
+        match self
+            .input_request
+            .as_ref()
+            .map(|request| request.prompt.clone())
+        {
+            Some(prompt) => self.show_input_prompt(&prompt),
+            None => self.prompt(),
+        }
+        if self.history_search.is_some() {
+            self.render_search();
         } else {
-            let send_back = if data.is_empty() {
-                self.prompt.to_string()
-            } else {
-                format!(
-                    "`{}` is not recognized as an internal or external command.\r\n{}",
-                    command, self.prompt,
-                )
+            self.redraw();
+        }
+    }
+
+    /// Start the resolved script command, `path` is the command name followed by the subcommand names.
+    fn start_script_command(
+        &mut self,
+        path: &str,
+        gd: &Gd<Command>,
+        words: Vec<String>,
+    ) -> ShExecuteStatus {
+        // The command with subcommands only dispatches to them.
+        if !gd.bind().subcommands().is_empty() {
+            let Some(word) = words.first() else {
+                self.sh_echo(CmdHelp::command_detail(&CommandHelp::from_command(
+                    path, gd,
+                )));
+                return ShExecuteStatus::Done;
             };
+            self.sh_echo(
+                ShAnsiString::new()
+                    .foreground_256(Color256::RED)
+                    .append(&format!("{}: unknown subcommand `{}`.", path, word))
+                    .clear_style()
+                    .crlf()
+                    .append(&format!("Type `help {}` for available subcommands.", path))
+                    .crlf(),
+            );
+            self.set_exit_code(EXIT_CODE_SYNTAX_ERROR);
+            return ShExecuteStatus::Failed;
+        }
 
-            let wstr = WideString::from_str(&send_back);
-            for &c in wstr.as_slice() {
-                #[allow(clippy::useless_transmute)]
-                let c: wchar_t = unsafe { std::mem::transmute(c) };
-                self.emulation.receive_char(c);
+        let specs = gd.bind().option_specs();
+        let status = if specs.is_empty() {
+            Command::start(gd.clone(), to_params(&words))
+        } else {
+            match parse_options(&specs, &words) {
+                Ok(ParsedOptions::Values(values)) => {
+                    Command::start_with_options(gd.clone(), options_dictionary(values))
+                }
+                Ok(ParsedOptions::Help) => {
+                    self.sh_echo(CmdHelp::command_detail(&CommandHelp::from_command(
+                        path, gd,
+                    )));
+                    return ShExecuteStatus::Done;
+                }
+                Err(e) => {
+                    self.sh_echo(
+                        ShAnsiString::new()
+                            .foreground_256(Color256::RED)
+                            .append(&format!("{}: {}.", path, e))
+                            .clear_style()
+                            .crlf()
+                            .append(&format!("Type `{} --help` for usage.", path))
+                            .crlf(),
+                    );
+                    self.set_exit_code(EXIT_CODE_SYNTAX_ERROR);
+                    return ShExecuteStatus::Failed;
+                }
             }
+        };
+        if status != ShExecuteStatus::Running {
+            self.pending_crlf = true;
+        }
+        status
+    }
+
+    #[inline]
+    fn update_exit_code(&mut self, status: ShExecuteStatus) {
+        // The input requested by the finished command is not waited anymore.
+        self.input_request = None;
+        self.raw_input = false;
+        let exit_code = self.pending_exit_code.take();
+        self.last_exit_code = match status {
+            ShExecuteStatus::Failed => exit_code.filter(|&code| code != 0).unwrap_or(1),
+            _ => 0,
+        };
+    }
 
-            self.cursor_origin = self.get_cursor_position();
-            self.columns = self.get_terminal_size().x;
+    /// Called when the running command finished, continue to execute the queued commands.
+    fn finish_running_command(&mut self, status: ShExecuteStatus) {
+        self.update_exit_code(status);
+        self.pending_crlf = true;
+        self.run_command_queue();
+    }
 
-            self.echos.extend(IpcEvent::pack_data(&send_back));
+    /// Edit the answer of the [`input_request`](Shell::input_request), only simple line editing is supported.
+    fn input_receive_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => self.submit_input(),
+            KeyCode::Backspace => {
+                if self.cursor > 0 {
+                    self.cursor = self.prev_char_boundary(self.cursor);
+                    self.buffer.truncate(self.cursor);
+                    self.redraw();
+                }
+            }
+            // Only the first line is taken as the answer.
+            KeyCode::Paste(text) => {
+                let text = paste_text(&text);
+                let line = text.lines().next().unwrap_or_default();
+                self.buffer.extend(string_to_wide(line));
+                self.cursor = self.buffer.len();
+                self.redraw();
+            }
+            _ => {
+                if let Some(c) = key.text().filter(|&c| is_printable(c)) {
+                    self.buffer
+                        .extend(string_to_wide(c.encode_utf8(&mut [0; 4])));
+                    self.cursor = self.buffer.len();
+                    self.redraw();
+                }
+            }
         }
     }
 
-    #[inline]
-    fn reset_argv(&mut self) {
-        self.argv[0] = 0;
-        self.argv[1] = 0;
-        self.argv[2] = 0;
+    /// Pass the key to `on_key()` of the running command with raw input enabled.
+    fn deliver_key(&mut self, key: KeyEvent) {
+        let key = key_dictionary(&key);
+        if let Some(icmd) = self.running_internal_command {
+            ptr_mut!(icmd).on_key(key);
+        } else if let Some(gd) = self.running_command.clone() {
+            Command::on_key(gd, key);
+        }
+    }
+
+    /// Deliver the answer of the [`input_request`](Shell::input_request) to the command,
+    /// ask again if the choice is invalid.
+    fn submit_input(&mut self) {
+        let Some(request) = self.input_request.take() else {
+            return;
+        };
+        let text = wide_to_string(&self.buffer);
+        self.buffer.clear();
+        self.cursor = 0;
+        self.next_line();
+
+        let answer = match &request.kind {
+            InputKind::Line => GString::from(text.as_str()).to_variant(),
+            InputKind::Confirm => {
+                matches!(text.trim().to_lowercase().as_str(), "y" | "yes").to_variant()
+            }
+            InputKind::Choose(options) => {
+                let text = text.trim();
+                let index = text
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|i| (1..=options.len()).contains(i))
+                    .map(|i| i - 1)
+                    .or_else(|| options.iter().position(|option| option == text));
+                match index {
+                    Some(index) => (index as i64).to_variant(),
+                    None => {
+                        self.sh_echo(
+                            ShAnsiString::new()
+                                .foreground_256(Color256::RED)
+                                .append(&format!("Invalid choice `{}`.", text))
+                                .clear_style()
+                                .crlf(),
+                        );
+                        self.show_input_prompt(&request.prompt);
+                        self.input_request = Some(request);
+                        return;
+                    }
+                }
+            }
+        };
+
+        Command::deliver_input(request.command, answer);
+    }
+
+    fn show_input_prompt(&mut self, prompt: &str) {
+        self.output(&format!("{}{}", ESC0M, prompt));
+        self.cursor_origin = self.get_cursor_position();
+        self.columns = self.get_terminal_size().x;
     }
 
     fn report_cursor(&mut self) {
@@ -592,9 +1721,12 @@ impl Shell {
         let text = format!("\x1B[{};{}H\x1B[K", cursor_origin.y, cursor_origin.x,);
         let mut text = WideString::from_str(&text);
 
-        #[allow(clippy::useless_transmute)]
-        let buffer: Vec<uwchar_t> = unsafe { std::mem::transmute(self.buffer.to_vec()) };
-        let mut cur_text = WideString::from_vec(buffer).to_string_lossy();
+        let mut cur_text = self.highlighted_text();
+        if let Some(suggestion) = self.suggestion() {
+            cur_text.push_str(ESC2M);
+            cur_text.push_str(&display_text(suggestion));
+            cur_text.push_str(ESC0M);
+        }
         let cursor_pos = self.cursor_to_position();
         cur_text.push_str(&format!("\x1B[{};{}H", cursor_pos.0, cursor_pos.1));
 
@@ -603,11 +1735,433 @@ impl Shell {
         text
     }
 
+    /// The text of buffer to display, colored by [`highlight`] if syntax highlighting is enabled.
+    fn highlighted_text(&self) -> String {
+        if !self.syntax_highlighting || self.input_request.is_some() {
+            return display_text(&self.buffer);
+        }
+
+        let text = wide_to_string(&self.buffer);
+        let is_command = |name: &str| {
+            self.internal_command_map.contains_key(name)
+                || self.command_map.contains_key(name)
+                || self.aliases.contains_key(name)
+        };
+
+        let mut highlighted = ShAnsiString::new();
+        for span in highlight(&text, &is_command) {
+            let part = text[span.start..span.end].replace('\n', NEWLINE_SYMBOL);
+            let color = match span.highlight {
+                Highlight::Plain => None,
+                Highlight::Command => Some(Color256::GREEN),
+                Highlight::UnknownCommand => Some(Color256::RED),
+                Highlight::Quoted => Some(Color256::YELLOW),
+                Highlight::Variable => Some(Color256::CYAN),
+                Highlight::Flag => Some(Color256::BRIGHT_BLUE),
+                Highlight::Operator => Some(Color256::MAGENTA),
+            };
+            highlighted = match color {
+                Some(color) => highlighted
+                    .foreground_256(color)
+                    .append(&part)
+                    .clear_style(),
+                None => highlighted.append(&part),
+            };
+        }
+        highlighted.as_str().to_string()
+    }
+
+    /// Redraw the whole input line and place the cursor at [`cursor`](Shell::cursor).
+    fn redraw(&mut self) {
+        let replay_text = self.replay_text();
+        self.echos
+            .extend(IpcEvent::pack_data(&replay_text.to_string_lossy()));
+
+        for &c in replay_text.as_slice() {
+            #[allow(clippy::useless_transmute)]
+            let c: wchar_t = unsafe { std::mem::transmute(c) };
+            self.emulation.receive_char(c);
+        }
+        self.map_set_cursor();
+    }
+
     #[inline]
+    fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+        self.report_cursor();
+        self.map_set_cursor();
+    }
+
+    /// Remove `buffer[start..end]` and save the removed text to the kill ring.
+    fn kill(&mut self, start: usize, end: usize) {
+        if start >= end {
+            return;
+        }
+
+        let killed: Vec<wchar_t> = self.buffer.drain(start..end).collect();
+        if self.kill_ring.len() >= KILL_RING_SIZE {
+            self.kill_ring.remove(0);
+        }
+        self.kill_ring.push(killed);
+
+        self.cursor = start;
+        self.redraw();
+    }
+
+    /// Insert the last killed text at cursor.
+    fn yank(&mut self) {
+        let text = match self.kill_ring.last() {
+            Some(text) => text.clone(),
+            None => return,
+        };
+
+        let len = text.len();
+        self.buffer.splice(self.cursor..self.cursor, text);
+        self.cursor += len;
+        self.redraw();
+    }
+
+    /// The rest of the most recent history command which starts with the input,
+    /// displayed dimmed after the input.
+    fn suggestion(&self) -> Option<&[wchar_t]> {
+        if !self.autosuggestions
+            || self.buffer.is_empty()
+            || self.continuation.is_some()
+            || self.input_request.is_some()
+        {
+            return None;
+        }
+
+        // The history commands are ordered by `u_stack` and then reversed `d_stack`.
+        self.d_stack
+            .iter()
+            .chain(self.u_stack.iter().rev())
+            .find(|command| command.len() > self.buffer.len() && command.starts_with(&self.buffer))
+            .map(|command| &command[self.buffer.len()..])
+    }
+
+    /// Append the suggestion to the input if the cursor is at the end of the input,
+    /// or only the next word of it if `word` is true.
+    ///
+    /// Returns false if there's no suggestion to accept.
+    fn accept_suggestion(&mut self, word: bool) -> bool {
+        if self.cursor != self.buffer.len() {
+            return false;
+        }
+        let Some(suggestion) = self.suggestion() else {
+            return false;
+        };
+
+        let mut end = suggestion.len();
+        if word {
+            end = 0;
+            while end < suggestion.len() && !is_word_char(suggestion[end]) {
+                end += 1;
+            }
+            while end < suggestion.len() && is_word_char(suggestion[end]) {
+                end += 1;
+            }
+        }
+
+        let accepted = suggestion[..end].to_vec();
+        self.buffer.extend(accepted);
+        self.cursor = self.buffer.len();
+        self.redraw();
+        true
+    }
+
+    /// Erase the suggestion displayed after the input, before the input line is finished.
+    fn erase_suggestion(&mut self) {
+        if self.suggestion().is_none() {
+            return;
+        }
+
+        self.cursor = self.buffer.len();
+        let (row, col) = self.cursor_to_position();
+        self.output(&format!("\x1B[{};{}H\x1B[K", row, col));
+    }
+
+    fn prev_word_boundary(&self, is_word: fn(wchar_t) -> bool) -> usize {
+        let mut pos = self.cursor;
+        while pos > 0 && !is_word(self.buffer[pos - 1]) {
+            pos -= 1;
+        }
+        while pos > 0 && is_word(self.buffer[pos - 1]) {
+            pos -= 1;
+        }
+        pos
+    }
+
+    fn next_word_boundary(&self, is_word: fn(wchar_t) -> bool) -> usize {
+        let mut pos = self.cursor;
+        while pos < self.buffer.len() && !is_word(self.buffer[pos]) {
+            pos += 1;
+        }
+        while pos < self.buffer.len() && is_word(self.buffer[pos]) {
+            pos += 1;
+        }
+        pos
+    }
+
+    /// The prompt represent as `host_name> `, the exit code is shown if the last command failed.
+    /// It's rendered from [`prompt_template`](Shell::prompt_template) instead if the template is set.
+    fn prompt_text(&self) -> String {
+        if self.continuation.is_some() {
+            return CONTINUATION_PROMPT.to_string();
+        }
+        if !self.prompt_template.is_empty() {
+            let prompt =
+                render_prompt(&self.prompt_template, &|name| self.prompt_placeholder(name));
+            return format!("{}{}\u{200B}", prompt, ESC0M);
+        }
+
+        let mut prompt = ShAnsiString::new().append(&self.host_name);
+        if self.last_exit_code != 0 {
+            prompt = prompt
+                .space()
+                .foreground_256(Color256::RED)
+                .append(&format!("[{}]", self.last_exit_code))
+                .clear_style();
+        }
+        prompt.append("> \u{200B}").as_str().to_string()
+    }
+
+    /// Value of the placeholder in [`prompt_template`](Shell::prompt_template).
+    fn prompt_placeholder(&self, name: &str) -> Option<String> {
+        let tree = || {
+            Engine::singleton()
+                .get_main_loop()
+                .and_then(|main_loop| main_loop.try_cast::<SceneTree>().ok())
+        };
+
+        let value = match name {
+            "host" => self.host_name.clone(),
+            "time" => Time::singleton().get_time_string_from_system().to_string(),
+            "fps" => Engine::singleton()
+                .get_frames_per_second()
+                .round()
+                .to_string(),
+            "scene" => tree()
+                .and_then(|tree| tree.get_current_scene())
+                .map(|scene| scene.get_name().to_string())
+                .unwrap_or_default(),
+            "last_status" => self.last_exit_code.to_string(),
+            "paused" => {
+                if tree().is_some_and(|tree| tree.is_paused()) {
+                    "paused".to_string()
+                } else {
+                    String::new()
+                }
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Send the text to the terminal, and keep [`emulation`](Shell::emulation) in sync.
+    fn output(&mut self, text: &str) {
+        self.echos.extend(IpcEvent::pack_data(text));
+
+        let wstr = WideString::from_str(text);
+        for &c in wstr.as_slice() {
+            #[allow(clippy::useless_transmute)]
+            let c: wchar_t = unsafe { std::mem::transmute(c) };
+            self.emulation.receive_char(c);
+        }
+    }
+
+    /// Append the executed command to the end of history, and save history to file if needed.
+    ///
+    /// Empty commands and commands same as the latest one are ignored.
+    fn add_history(&mut self, command: Vec<wchar_t>) {
+        // Rewind the history navigation, so the new command is always the latest one.
+        while let Some(command) = self.d_stack.pop() {
+            self.u_stack.push(command);
+        }
+
+        if command.is_empty() || self.u_stack.last() == Some(&command) {
+            return;
+        }
+        self.u_stack.push(command);
+        if self.u_stack.len() > self.history_size {
+            let overflow = self.u_stack.len() - self.history_size;
+            self.u_stack.drain(..overflow);
+        }
+
+        if let Some(path) = self.history_path.as_ref() {
+            let history: Vec<String> = self.u_stack.iter().map(|c| wide_to_string(c)).collect();
+            save_history(path, &history);
+        }
+    }
+
+    /// All the history commands, from the oldest to the latest.
+    fn history(&self) -> Vec<&Vec<wchar_t>> {
+        self.u_stack
+            .iter()
+            .chain(self.d_stack.iter().rev())
+            .collect()
+    }
+
+    fn start_search(&mut self) {
+        self.history_search = Some(HistorySearch {
+            query: vec![],
+            matched: None,
+            saved_buffer: self.buffer.clone(),
+            saved_cursor: self.cursor,
+        });
+        self.render_search();
+    }
+
+    /// Handle the key in search mode,
+    /// return false if the key should be handled as normal input after the search finished.
+    fn search_receive_key(&mut self, key: &KeyEvent) -> bool {
+        if *key == ctrl_key('r') {
+            let search = self.history_search.as_ref().unwrap();
+            let end = search.matched.unwrap_or(usize::MAX);
+            // Keep the current match if there is no older one.
+            if let Some(idx) = self.search_history(end) {
+                self.history_search.as_mut().unwrap().matched = Some(idx);
+            }
+            self.render_search();
+            return true;
+        }
+        if *key == ctrl_key('g') || *key == ctrl_key('c') || *key == KeyEvent::new(KeyCode::Escape)
+        {
+            self.finish_search(false);
+            return true;
+        }
+        if *key == KeyEvent::new(KeyCode::Backspace) {
+            let query = &mut self.history_search.as_mut().unwrap().query;
+            if query.pop().is_some_and(is_low_surrogate)
+                && query.last().is_some_and(|&c| is_high_surrogate(c))
+            {
+                query.pop();
+            }
+            let matched = self.search_history(usize::MAX);
+            self.history_search.as_mut().unwrap().matched = matched;
+            self.render_search();
+            return true;
+        }
+        if let Some(c) = key.text().filter(|&c| is_printable(c)) {
+            let search = self.history_search.as_mut().unwrap();
+            search
+                .query
+                .extend(string_to_wide(c.encode_utf8(&mut [0; 4])));
+            // The current match is still preferred if it contains the extended query.
+            let end = search.matched.map(|idx| idx + 1).unwrap_or(usize::MAX);
+            let matched = self.search_history(end);
+            self.history_search.as_mut().unwrap().matched = matched;
+            self.render_search();
+            return true;
+        }
+
+        // Accept the matched command and handle the key normally.
+        self.finish_search(true);
+        false
+    }
+
+    /// Search the latest history command which contains the query, in the range of `history[..end]`.
+    fn search_history(&self, end: usize) -> Option<usize> {
+        let query = &self.history_search.as_ref()?.query;
+        if query.is_empty() {
+            return None;
+        }
+
+        let history = self.history();
+        (0..end.min(history.len())).rev().find(|&i| {
+            history[i]
+                .windows(query.len())
+                .any(|w| w == query.as_slice())
+        })
+    }
+
+    fn render_search(&mut self) {
+        let search = self.history_search.as_ref().unwrap();
+        let query = wide_to_string(&search.query);
+        let (label, matched) = match search.matched {
+            Some(idx) => ("reverse-i-search", display_text(self.history()[idx])),
+            None if query.is_empty() => ("reverse-i-search", String::new()),
+            None => ("failed reverse-i-search", String::new()),
+        };
+
+        let text = format!(
+            "\x1B[{};1H\x1B[J({})`{}': {}",
+            self.cursor_origin.y, label, query, matched
+        );
+        self.output(&text);
+    }
+
+    /// Leave the search mode, the matched command will be placed in buffer if `accept` is true,
+    /// otherwise the previous buffer will be restored.
+    fn finish_search(&mut self, accept: bool) {
+        let search = match self.history_search.take() {
+            Some(search) => search,
+            None => return,
+        };
+
+        match search.matched {
+            Some(idx) if accept => {
+                self.buffer = self.history()[idx].clone();
+                self.cursor = self.buffer.len();
+            }
+            _ => {
+                self.buffer = search.saved_buffer;
+                self.cursor = search.saved_cursor;
+            }
+        }
+
+        let text = format!("\x1B[{};1H\x1B[J", self.cursor_origin.y);
+        self.output(&text);
+        self.prompt();
+        self.redraw();
+    }
+
+    /// Screen position of the cursor represent as (row, col), the origin point is (1, 1).
+    ///
+    /// Characters take their display cell width, and the input wraps at the terminal width,
+    /// a wide character not fitting in the rest of the line is moved to the next line.
     fn cursor_to_position(&self) -> (i32, i32) {
-        let row = (self.cursor as i32 + self.cursor_origin.x) / (self.columns + 1);
-        let col = (self.cursor as i32 + self.cursor_origin.x) % (self.columns + 1);
-        (self.cursor_origin.y + row, col)
+        let columns = self.columns.max(1);
+        let (mut row, mut col) = (self.cursor_origin.y, self.cursor_origin.x);
+        for c in display_text(&self.buffer[..self.cursor]).chars() {
+            let width = c.width().unwrap_or(0) as i32;
+            if col + width - 1 > columns {
+                row += 1;
+                col = 1;
+            }
+            col += width;
+        }
+        if col > columns {
+            row += 1;
+            col = 1;
+        }
+        (row, col)
+    }
+
+    /// Index of the previous character in buffer, surrogate pair is treated as one character.
+    #[inline]
+    fn prev_char_boundary(&self, pos: usize) -> usize {
+        if pos >= 2
+            && is_low_surrogate(self.buffer[pos - 1])
+            && is_high_surrogate(self.buffer[pos - 2])
+        {
+            pos - 2
+        } else {
+            pos.saturating_sub(1)
+        }
+    }
+
+    /// Index of the next character in buffer, surrogate pair is treated as one character.
+    #[inline]
+    fn next_char_boundary(&self, pos: usize) -> usize {
+        if pos + 1 < self.buffer.len()
+            && is_high_surrogate(self.buffer[pos])
+            && is_low_surrogate(self.buffer[pos + 1])
+        {
+            pos + 2
+        } else {
+            (pos + 1).min(self.buffer.len())
+        }
     }
 
     #[inline]
@@ -623,87 +2177,144 @@ impl Shell {
         let buffer: Vec<uwchar_t> = unsafe { std::mem::transmute(self.buffer.to_vec()) };
         let input = WideString::from_vec(buffer).to_string_lossy();
         let mut echo = String::new();
-        let mut prompt = false;
         if input.is_empty() {
-            let commands: Vec<&str> = self.command_map.keys().map(|c| c.as_str()).collect();
-            if commands.is_empty() {
-                let cursor_pos = self.get_cursor_position();
-                echo.push_str(&format!("\x1B[{};{}H", cursor_pos.y, cursor_pos.x));
-            } else {
+            let commands = self.command_names();
+            let commands: Vec<&str> = commands.iter().map(|c| c.as_str()).collect();
+            if !commands.is_empty() {
                 echo.push_str(&self.format_commands_list(&commands));
-                prompt = true;
             }
-        } else if input.len() != self.cursor {
+        } else if self.buffer.len() != self.cursor {
             // Do nothing
         } else {
-            let mut commands: Vec<&str> = self
-                .command_map
-                .keys()
-                .filter_map(|cmd| {
-                    if cmd.starts_with(&input) {
-                        Some(cmd.as_str())
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+            let (start, mut candidates) = self.completion_candidates(&input);
 
-            match commands.len().cmp(&1) {
+            match candidates.len().cmp(&1) {
                 Ordering::Greater => {
-                    echo.push_str(&self.format_commands_list(&commands));
-                    prompt = true;
+                    let candidates: Vec<&str> = candidates.iter().map(|c| c.as_str()).collect();
+                    echo.push_str(&self.format_commands_list(&candidates));
                 }
                 Ordering::Equal => {
-                    let origin = self.cursor_origin;
-                    let cmd = commands.pop().unwrap();
-                    echo.push_str(&format!("\x1B[{};{}H{}", origin.y, origin.x, cmd));
-
-                    let wstr = WideString::from_str(cmd);
-                    #[allow(clippy::useless_transmute)]
-                    let buffer: Vec<wchar_t> =
-                        unsafe { std::mem::transmute(wstr.as_slice().to_vec()) };
-                    self.buffer = buffer;
+                    let completed =
+                        format!("{}{}", &input[..start], quote(&candidates.pop().unwrap()));
+                    self.buffer = string_to_wide(&completed);
                     self.cursor = self.buffer.len();
+                    self.redraw();
                 }
                 Ordering::Less => {}
             }
         }
 
-        if prompt {
-            echo.push_str(&format!("\r\n{}", self.prompt));
+        if echo.is_empty() {
+            return;
         }
 
-        if !echo.is_empty() {
-            for e in IpcEvent::pack_data(&echo) {
-                self.echos.push_back(e);
-            }
+        // Print the candidates list, then prompt again with the input unchanged.
+        echo.push_str(&format!("\r\n{}", self.prompt_text()));
+        self.echos.extend(IpcEvent::pack_data(&echo));
 
-            let wstr = WideString::from_str(&echo);
-            for &c in wstr.as_slice() {
-                #[allow(clippy::useless_transmute)]
-                let c: wchar_t = unsafe { std::mem::transmute(c) };
-                self.emulation.receive_char(c);
-            }
+        let wstr = WideString::from_str(&echo);
+        for &c in wstr.as_slice() {
+            #[allow(clippy::useless_transmute)]
+            let c: wchar_t = unsafe { std::mem::transmute(c) };
+            self.emulation.receive_char(c);
+        }
 
-            if prompt {
-                self.cursor_origin = self.get_cursor_position();
+        self.cursor_origin = self.get_cursor_position();
+        self.redraw();
+    }
 
-                if input.is_empty() {
-                    return;
-                }
+    /// Get the candidates to complete the last word of input,
+    /// and the byte offset where the last word starts.
+    ///
+    /// The first word is completed by command names, the others are completed by the command itself.
+    fn completion_candidates(&mut self, input: &str) -> (usize, Vec<String>) {
+        let mut tokens = tokenize_partial(input);
+        let new_word = tokens
+            .last()
+            .is_none_or(|t| t.end < input.len() || t.operator.is_some());
+
+        // Only the last command of the chained commands is completed.
+        if let Some(idx) = tokens.iter().rposition(|t| t.operator.is_some()) {
+            tokens.drain(..=idx);
+            if tokens.is_empty() {
+                return (input.len(), self.command_names());
+            }
+        }
 
-                for e in IpcEvent::pack_data(&input) {
-                    self.echos.push_back(e);
-                }
+        if tokens.len() == 1 && !new_word {
+            let word = tokens.pop().unwrap();
+            let candidates = self
+                .command_names()
+                .into_iter()
+                .filter(|cmd| cmd.starts_with(&word.text))
+                .collect();
+            return (word.start, candidates);
+        }
+        if tokens.is_empty() {
+            return (0, vec![]);
+        }
 
-                let wstr = WideString::from_str(&input);
-                for &c in wstr.as_slice() {
-                    #[allow(clippy::useless_transmute)]
-                    let c: wchar_t = unsafe { std::mem::transmute(c) };
-                    self.emulation.receive_char(c);
+        let (prefix, start) = if new_word {
+            (String::new(), input.len())
+        } else {
+            let word = tokens.pop().unwrap();
+            (word.text, word.start)
+        };
+        let command = tokens.remove(0).text;
+        let mut words: Vec<String> = tokens.into_iter().map(|t| t.text).collect();
+
+        let candidates = if let Some(icmd) = self.internal_command_map.get_mut(&command) {
+            words.push(prefix.clone());
+            let index = words.len() - 1;
+            icmd.complete(to_params(&words), index)
+        } else if let Some(gd) = self.command_map.get(&command) {
+            let (gd, depth) = Command::resolve(gd, &words);
+            let subcommands = gd.bind().subcommands();
+            if !subcommands.is_empty() {
+                if depth < words.len() {
+                    return (start, vec![]);
                 }
+                subcommands
+                    .iter()
+                    .map(|sub| sub.bind().get_command_name())
+                    .collect()
+            } else {
+                words.drain(..depth);
+                words.push(prefix.clone());
+                let index = words.len() - 1;
+
+                let mut candidates = Command::complete(gd.clone(), to_params(&words), index as i32);
+                let specs = gd.bind().option_specs();
+                if !specs.is_empty() {
+                    for candidate in complete_options(&specs, &words, index) {
+                        candidates.push(&GString::from(candidate.as_str()));
+                    }
+                }
+                candidates
             }
-        }
+        } else {
+            return (start, vec![]);
+        };
+
+        let candidates = candidates
+            .iter_shared()
+            .map(|c| c.to_string())
+            .filter(|c| c.starts_with(&prefix))
+            .collect();
+        (start, candidates)
+    }
+
+    /// Names of the commands and aliases, which are candidates of the first word.
+    fn command_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .command_map
+            .keys()
+            .chain(self.aliases.keys())
+            .cloned()
+            .collect();
+        names.sort();
+        names.dedup();
+        names
     }
 
     fn format_commands_list(&self, commands: &[&str]) -> String {
@@ -739,3 +2350,70 @@ impl Shell {
         }
     }
 }
+
+/// Prefix every line of the background job output with the job id, e.g. `[1] `.
+fn tag_lines(id: usize, text: &str) -> String {
+    text.trim_end_matches(['\r', '\n'])
+        .split('\n')
+        .map(|line| format!("[{}] {}{}\r\n", id, line.trim_end_matches('\r'), ESC0M))
+        .collect()
+}
+
+/// The key passed to `on_key()` of commands, e.g. `{ "key": "Left", "text": "", "ctrl": true, "alt": false, "shift": false }`.
+fn key_dictionary(key: &KeyEvent) -> Dictionary {
+    let name = key.code.to_string();
+    let text = match &key.code {
+        KeyCode::Paste(text) => text.clone(),
+        _ => key.text().map(String::from).unwrap_or_default(),
+    };
+
+    let mut dict = Dictionary::new();
+    dict.set("key", GString::from(name.as_str()));
+    dict.set("text", GString::from(text.as_str()));
+    dict.set("ctrl", key.modifiers.ctrl);
+    dict.set("alt", key.modifiers.alt);
+    dict.set("shift", key.modifiers.shift);
+    dict
+}
+
+/// The key of Control+`c`.
+#[inline]
+fn ctrl_key(c: char) -> KeyEvent {
+    KeyEvent::with(
+        KeyCode::Char(c),
+        Modifiers {
+            ctrl: true,
+            ..Default::default()
+        },
+    )
+}
+
+#[inline]
+fn to_params(words: &[String]) -> Array<GString> {
+    let mut params = array![];
+    for word in words {
+        params.push(&GString::from_str(word).unwrap());
+    }
+    params
+}
+
+#[inline]
+fn wide_to_string(wstr: &[wchar_t]) -> String {
+    #[allow(clippy::useless_transmute)]
+    let buffer: Vec<uwchar_t> = unsafe { std::mem::transmute(wstr.to_vec()) };
+    WideString::from_vec(buffer).to_string_lossy()
+}
+
+/// The text of buffer to display, line breaks are replaced by [`NEWLINE_SYMBOL`].
+#[inline]
+fn display_text(buffer: &[wchar_t]) -> String {
+    wide_to_string(buffer).replace('\n', NEWLINE_SYMBOL)
+}
+
+#[inline]
+fn string_to_wide(str: &str) -> Vec<wchar_t> {
+    let wstr = WideString::from_str(str);
+    #[allow(clippy::useless_transmute)]
+    let wstr: Vec<wchar_t> = unsafe { std::mem::transmute(wstr.into_vec()) };
+    wstr
+}
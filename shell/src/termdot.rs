@@ -1,7 +1,7 @@
 use crate::{
     command::{Command, internal::log::CmdLog},
     consoel_captures::ConsoleCaptures,
-    shell::Shell,
+    shell::{SHELL, Shell},
 };
 use common::{constants::REGISTER_HEAT_BEAT_DURATION, gb_error, typedef::RegisterInfoId};
 use godot::{
@@ -19,7 +19,7 @@ use std::{
     time::Instant,
 };
 use termio::cli::session::SessionPropsId;
-use tmui::tlib::{global::SemanticExt, utils::SnowflakeGuidGenerator};
+use tmui::tlib::{global::SemanticExt, nonnull_mut, utils::SnowflakeGuidGenerator};
 use wchar::wchar_t;
 use widestring::WideString;
 
@@ -48,6 +48,19 @@ pub fn terminal_version() -> &'static str {
     TERMINAL_VERSION.with(|br| Box::leak(br.borrow().clone().boxed()))
 }
 
+/// Warn about the empty `options` slots of the command and its subcommands, which are ignored.
+fn warn_empty_options(command: &Gd<Command>) {
+    if command.bind().has_empty_option() {
+        godot_warn!(
+            "[Termdot::ready] The `options` of Command {} has an empty slot, ignore the slot.",
+            command.get_name()
+        );
+    }
+    for sub in command.bind().subcommands() {
+        warn_empty_options(&sub);
+    }
+}
+
 #[cfg(windows_platform)]
 pub const APP_PATH: [&str; 2] = ["res://addons/termdot/termdot.exe", "res://termdot.exe"];
 #[cfg(macos_platform)]
@@ -64,6 +77,13 @@ pub struct Termdot {
     #[init(val = GString::from_str("termdot").unwrap())]
     host_name: GString,
 
+    #[export]
+    /// Template of the prompt, rendered every time the prompt is shown, e.g. `{green}{host}{reset}:{scene}> `.
+    /// Placeholders: `{host}`, `{time}`, `{fps}`, `{scene}`, `{last_status}` and `{paused}`,
+    /// color markup: `{red}`, `{bright_red}` etc., `{bold}`, `{dim}` and `{reset}`.
+    /// The default `host_name> ` prompt is used if it's empty.
+    prompt_template: GString,
+
     #[export(range = (1., 60.))]
     /// Commands execution frequency
     #[init(val = 60)]
@@ -73,7 +93,39 @@ pub struct Termdot {
     #[init(val = true)]
     auto_output_captures: bool,
 
+    #[export]
+    /// File to persist the command history across game runs, empty to disable the persistence.
+    #[init(val = GString::from_str("user://termdot_history").unwrap())]
+    history_path: GString,
+
+    #[export(range = (0., 10000.))]
+    /// Maximum number of commands kept in history.
+    #[init(val = 500)]
+    history_size: u32,
+
+    #[export]
+    /// Script file executed automatically when the session started, empty to disable it.
+    #[init(val = GString::from_str("user://autoexec.cfg").unwrap())]
+    autoexec_path: GString,
+
+    #[export]
+    /// Color the command names, quoted strings, variables and flags of the input line.
+    #[init(val = true)]
+    syntax_highlighting: bool,
+
+    #[export]
+    /// Show the most recent history command starting with the input as dim text after the cursor.
+    #[init(val = true)]
+    autosuggestions: bool,
+
+    #[export]
+    /// Wait for Enter before running the multiple lines pasted at once, otherwise they run immediately.
+    #[init(val = true)]
+    confirm_multiline_paste: bool,
+
     accumulator: f64,
+    /// Trailing bytes of the incomplete UTF-8 character received from terminal.
+    incomplete_utf8: Vec<u8>,
 
     #[init(val = ConsoleCaptures::new())]
     console_captures: ConsoleCaptures,
@@ -110,11 +162,27 @@ impl INode for Termdot {
                     );
                     continue;
                 }
+                if self.shell.has_internal_command(&name) {
+                    godot_warn!(
+                        "[Termdot::ready] The `command_name` of Command {} conflicts with internal command `{}`, ignore command register.",
+                        command.get_name(),
+                        name
+                    );
+                    continue;
+                }
+                warn_empty_options(&command);
                 self.shell.insert_command(name, command);
             }
         }
 
         self.shell.set_prompt(&self.host_name.to_string());
+        self.shell
+            .set_prompt_template(&self.prompt_template.to_string());
+        self.shell.set_syntax_highlighting(self.syntax_highlighting);
+        self.shell.set_autosuggestions(self.autosuggestions);
+        self.shell.set_confirm_paste(self.confirm_multiline_paste);
+        self.shell
+            .set_history_file(&self.history_path.to_string(), self.history_size as usize);
 
         self.ipc_context = IpcContext::shell();
         if self.ipc_context.is_none() {
@@ -146,10 +214,12 @@ impl INode for Termdot {
 
         self.shell.check_buffer_storage();
 
-        while let Some(evt) = self.ipc_channel.as_ref().unwrap().try_recv() {
+        while let Some(evt) = self.ipc_channel.as_ref().and_then(|c| c.try_recv()) {
             match evt {
                 IpcEvent::HeartBeat => {}
                 IpcEvent::RequestExit => {
+                    self.shell.set_bracketed_paste(false);
+                    self.flush_echos();
                     if let Some(channel) = self.ipc_channel.as_ref() {
                         let _ = channel.try_send(IpcEvent::Exit);
                     }
@@ -166,12 +236,7 @@ impl INode for Termdot {
             }
         }
 
-        let ipc_channel = self.ipc_channel.as_ref().unwrap();
-        while let Some(echo) = self.shell.next_echo() {
-            if let Err(e) = ipc_channel.try_send(echo) {
-                godot_error!("[Termdot::process] Send echo failed, e = {:?}", e);
-            }
-        }
+        self.flush_echos();
     }
 
     fn exit_tree(&mut self) {
@@ -218,14 +283,55 @@ impl Termdot {
     pub fn error(log: GString) {
         CmdLog::error(log.to_string());
     }
+
+    #[func]
+    /// Set the shell variable, which can be referenced as `$name` or `${name}` in command line.
+    pub fn set_var(name: GString, value: GString) {
+        SHELL.with(|rf| {
+            if rf.borrow().is_none() {
+                godot_warn!(
+                    "[Termdot::set_var] Shell is not ready, ignore setting `{}`.",
+                    name
+                );
+                return;
+            }
+            if !nonnull_mut!(rf.borrow_mut()).set_var(&name.to_string(), &value.to_string()) {
+                godot_warn!(
+                    "[Termdot::set_var] `{}` is not a valid variable name.",
+                    name
+                );
+            }
+        })
+    }
+
+    #[func]
+    /// Get the shell variable, return empty string if the variable doesn't exist.
+    pub fn get_var(name: GString) -> GString {
+        SHELL.with(|rf| {
+            if rf.borrow().is_none() {
+                return GString::new();
+            }
+            nonnull_mut!(rf.borrow_mut())
+                .get_var(&name.to_string())
+                .map(|value| GString::from(value.as_str()))
+                .unwrap_or_default()
+        })
+    }
 }
 
 impl Termdot {
     fn recv_data(&mut self, data: &[u8; IPC_DATA_SIZE], len: usize) {
-        let mut data = data.to_vec();
-        data.truncate(len);
-        let data = match String::from_utf8(data) {
+        let mut bytes = std::mem::take(&mut self.incomplete_utf8);
+        bytes.extend_from_slice(&data[..len]);
+        let data = match String::from_utf8(bytes) {
             Ok(d) => d,
+            // Multi-byte character split by the IPC packet, keep the incomplete bytes for the next packet.
+            Err(e) if e.utf8_error().error_len().is_none() => {
+                let valid_up_to = e.utf8_error().valid_up_to();
+                let mut bytes = e.into_bytes();
+                self.incomplete_utf8 = bytes.split_off(valid_up_to);
+                String::from_utf8(bytes).unwrap()
+            }
             Err(e) => {
                 godot_error!(
                     "[Termdot::recv_data] Parse utf-8 string failed, err = {:?}",
@@ -245,6 +351,7 @@ impl Termdot {
             let c: wchar_t = unsafe { std::mem::transmute(c) };
             self.shell.receive_char(c);
         }
+        self.shell.flush_escape();
 
         self.shell.echo_replay_text();
     }
@@ -266,12 +373,26 @@ impl Termdot {
     }
 
     fn termdot_exit(&mut self) {
+        self.shell.set_bracketed_paste(false);
+        self.flush_echos();
         if let Some(channel) = self.ipc_channel.as_ref() {
             let _ = channel.try_send(IpcEvent::Exit);
         }
         self.ipc_channel = None;
     }
 
+    /// Send the echos of shell to terminal, they're dropped if the session is closed by `IpcEvent::Exit`.
+    fn flush_echos(&mut self) {
+        let Some(ipc_channel) = self.ipc_channel.as_ref() else {
+            return;
+        };
+        while let Some(echo) = self.shell.next_echo() {
+            if let Err(e) = ipc_channel.try_send(echo) {
+                godot_error!("[Termdot::flush_echos] Send echo failed, e = {:?}", e);
+            }
+        }
+    }
+
     fn send_ipc_event(&self, event: IpcEvent) {
         if let Some(ipc_ctx) = self.ipc_channel.as_ref() {
             if let Err(e) = ipc_ctx.try_send(event) {
@@ -350,8 +471,9 @@ impl Termdot {
             session_id,
             &self.host_name.to_string(),
         ));
-        self.shell.prompt();
         self.session_id = session_id;
+        self.shell.set_bracketed_paste(true);
+        self.shell.autoexec(&self.autoexec_path.to_string());
     }
 
     fn heart_beat_to_context(&mut self) {
@@ -303,6 +303,12 @@ impl AnsiString {
         self.to_gd()
     }
 
+    #[func]
+    pub fn clear_cursor_to_screen_end(&mut self) -> Gd<Self> {
+        self.builder.push_str(ESC0J);
+        self.to_gd()
+    }
+
     #[func]
     pub fn clear_line(&mut self) -> Gd<Self> {
         self.builder.push_str(ESC2K);
@@ -366,6 +366,12 @@ impl ShAnsiString {
         self
     }
 
+    #[inline]
+    pub fn clear_cursor_to_screen_end(mut self) -> Self {
+        self.builder.push_str(ESC0J);
+        self
+    }
+
     #[inline]
     pub fn clear_line(mut self) -> Self {
         self.builder.push_str(ESC2K);
@@ -1,4 +1,4 @@
-use wchar::{wch, wchar_t};
+use wchar::wchar_t;
 
 /// Common character str
 pub const DOT: &str = ".";
@@ -22,55 +22,40 @@ pub const UNICODE_BULLET: wchar_t = 0x2022; // Bullet point
 // Common control character code points:
 /// Null character (NUL)
 pub const CTL_NULL: wchar_t = 0x0000; 
-/// Bell (BEL)
+/// Bell (BEL), Control+G
 pub const CTL_BELL: wchar_t = 0x0007; 
-/// Backspace (BS)
-pub const CTL_BACKSPACE: wchar_t = 0x0008; 
-/// Tab (TAB)
-pub const CTL_TAB: wchar_t = 0x0009; 
-/// Line feed (LF)
-pub const CTL_NEWLINE: wchar_t = 0x000A; 
-/// Carriage return (CR \r)
-pub const CTL_CARRIAGE_RETURN: wchar_t = 0x000D;
-/// ESC
-pub const CTL_ESCAPE: wchar_t = 0x001B; 
 /// Control+C
 pub const CTL_SIGINT: wchar_t = 0x0003;
-/// Single shift 3
-pub const CTL_SS3: wchar_t = wch!('O');
 
-// Common keyboard control character code points:
-/// ↑ `A` at the end of escape sequence \x1B[A
-pub const KEY_UP: wchar_t = 0x0041;
-/// ↓ `B` at the end of escape sequence \x1B[B
-pub const KEY_DOWN: wchar_t = 0x0042; 
-/// → `C` at the end of escape sequence \x1B[C
-pub const KEY_RIGHT: wchar_t = 0x0043; 
-/// ← `D` at the end of escape sequence \x1B[D
-pub const KEY_LEFT: wchar_t = 0x0044; 
-/// `H` at the end of escape sequence \X1B[H
-pub const KEY_HOME: wchar_t = 0x0048;
-/// `F` at the end of escape sequence \X1B[F
-pub const KEY_END: wchar_t = 0x0046;
+/// Determines if a character is printable, which is any Unicode character except the control characters.
+///
+/// The characters are decoded by [`KeyDecoder`](crate::utils::key::KeyDecoder) first,
+/// so where `wchar_t` is UTF-16 (Windows) the characters outside of BMP arrive here as a whole.
+#[inline]
+pub fn is_printable(ch: char) -> bool {
+    !ch.is_control()
+}
+
+/// Determines if a character is the leading code unit of UTF-16 surrogate pair.
+#[inline]
+pub fn is_high_surrogate(ch: wchar_t) -> bool {
+    (0xD800..=0xDBFF).contains(&(ch as u32))
+}
 
-/// Determines if a character is printable
-pub fn is_printable(ch: wchar_t) -> bool {
-    match ch {
-        // Printable ASCII characters (0x20-0x7E)
-        0x0020..=0x007E => true,
-        
-        // Common Unicode printable characters
-        0x00A0..=0x00FF | // Latin-1 Supplement
-        0x2000..=0x206F | // Punctuation
-        0x3000..=0x30FF | // CJK symbols and Japanese kana
-        0x4E00..=0x9FFF   // Common CJK ideographs
-        => true,
-        
-        _ => false,
-    }
+/// Determines if a character is the trailing code unit of UTF-16 surrogate pair.
+#[inline]
+pub fn is_low_surrogate(ch: wchar_t) -> bool {
+    (0xDC00..=0xDFFF).contains(&(ch as u32))
 }
 
+/// Determines if a character is part of an alphanumeric word, used by Alt+B/F and Alt+Backspace.
 #[inline]
-pub fn is_csi_final_byte(ch: wchar_t) -> bool {
-    (0x40..=0x7E).contains(&ch)
-}
\ No newline at end of file
+pub fn is_word_char(ch: wchar_t) -> bool {
+    char::from_u32(ch as u32).is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Determines if a character is not whitespace, used by Control+W.
+#[inline]
+pub fn is_non_blank(ch: wchar_t) -> bool {
+    char::from_u32(ch as u32).is_some_and(|c| !c.is_whitespace())
+}
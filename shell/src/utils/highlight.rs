@@ -0,0 +1,192 @@
+use super::tokenizer::tokenize_partial;
+
+/// Syntax category of a part of the command line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Highlight {
+    Plain,
+    /// First word of a command which is resolved.
+    Command,
+    /// First word of a command which is not found.
+    UnknownCommand,
+    /// Quoted string, including the quotes.
+    Quoted,
+    /// Parameter expansion like `$name`, `${name}` or `$?`.
+    Variable,
+    /// Word starts with `-`.
+    Flag,
+    /// Control operator `;`, `&&`, `||` or the trailing `&`.
+    Operator,
+}
+
+/// A highlighted part of the command line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    /// Byte offset of the start of the span in the input.
+    pub start: usize,
+    /// Byte offset of the end of the span in the input.
+    pub end: usize,
+    pub highlight: Highlight,
+}
+
+/// Split the (possibly incomplete) command line into highlighted spans,
+/// which cover the whole input in order.
+///
+/// `is_command` determines if the first word of a command resolves.
+pub fn highlight(input: &str, is_command: &dyn Fn(&str) -> bool) -> Vec<Span> {
+    let mut marks = vec![Highlight::Plain; input.len()];
+
+    let mut command_start = true;
+    for token in tokenize_partial(input) {
+        let highlight = if token.operator.is_some() {
+            command_start = true;
+            Highlight::Operator
+        } else if std::mem::take(&mut command_start) {
+            if is_command(&token.text) {
+                Highlight::Command
+            } else {
+                Highlight::UnknownCommand
+            }
+        } else if input[token.start..].starts_with('-') {
+            Highlight::Flag
+        } else {
+            continue;
+        };
+        marks[token.start..token.end].fill(highlight);
+    }
+
+    mark_quotes(input, &mut marks);
+
+    let mut spans: Vec<Span> = vec![];
+    for (i, &highlight) in marks.iter().enumerate() {
+        match spans.last_mut() {
+            Some(span) if span.highlight == highlight => span.end = i + 1,
+            _ => spans.push(Span {
+                start: i,
+                end: i + 1,
+                highlight,
+            }),
+        }
+    }
+    spans
+}
+
+/// Mark the quoted strings and parameter expansions, the command names keep their highlight.
+fn mark_quotes(input: &str, marks: &mut [Highlight]) {
+    let mut mark = |start: usize, end: usize, highlight: Highlight| {
+        for m in &mut marks[start..end] {
+            if !matches!(m, Highlight::Command | Highlight::UnknownCommand) {
+                *m = highlight;
+            }
+        }
+    };
+
+    let bytes = input.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\'' => {
+                let end = input[i + 1..].find('\'').map_or(input.len(), |p| i + p + 2);
+                mark(i, end, Highlight::Quoted);
+                i = end;
+            }
+            b'"' => {
+                let start = i;
+                i += 1;
+                let mut variables = vec![];
+                while i < bytes.len() && bytes[i] != b'"' {
+                    match bytes[i] {
+                        b'\\' => i += 2,
+                        b'$' => {
+                            let end = parameter_end(input, i);
+                            variables.push((i, end));
+                            i = end.max(i + 1);
+                        }
+                        _ => i += 1,
+                    }
+                }
+                i = (i + 1).min(input.len());
+                mark(start, i, Highlight::Quoted);
+                for (start, end) in variables {
+                    mark(start, end, Highlight::Variable);
+                }
+            }
+            b'$' => {
+                let end = parameter_end(input, i);
+                mark(i, end, Highlight::Variable);
+                i = end.max(i + 1);
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+/// Byte offset of the end of the parameter expansion starting with `$` at `start`,
+/// `start` itself if it's not an expansion.
+fn parameter_end(input: &str, start: usize) -> usize {
+    let rest = &input[start + 1..];
+    match rest.as_bytes().first() {
+        Some(b'?') => start + 2,
+        Some(b'{') => rest.find('}').map_or(input.len(), |p| start + p + 2),
+        Some(c) if c.is_ascii_alphabetic() || *c == b'_' => {
+            let len = rest
+                .bytes()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == b'_')
+                .count();
+            start + 1 + len
+        }
+        _ => start,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(input: &str) -> Vec<(&str, Highlight)> {
+        highlight(input, &|name| name == "echo")
+            .into_iter()
+            .map(|s| (&input[s.start..s.end], s.highlight))
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight() {
+        use Highlight::*;
+
+        assert!(spans("").is_empty());
+        assert_eq!(
+            spans("echo -n 'a b' $x && ech"),
+            vec![
+                ("echo", Command),
+                (" ", Plain),
+                ("-n", Flag),
+                (" ", Plain),
+                ("'a b'", Quoted),
+                (" ", Plain),
+                ("$x", Variable),
+                (" ", Plain),
+                ("&&", Operator),
+                (" ", Plain),
+                ("ech", UnknownCommand),
+            ]
+        );
+        assert_eq!(
+            spans(r#"echo "hi $name!" ${a}b $ \"x"#),
+            vec![
+                ("echo", Command),
+                (" ", Plain),
+                (r#""hi "#, Quoted),
+                ("$name", Variable),
+                (r#"!""#, Quoted),
+                (" ", Plain),
+                ("${a}", Variable),
+                (r#"b $ \"x"#, Plain),
+            ]
+        );
+        assert_eq!(
+            spans("\"echo\" 'unclosed"),
+            vec![("\"echo\"", Command), (" ", Plain), ("'unclosed", Quoted)]
+        );
+    }
+}
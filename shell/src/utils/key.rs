@@ -0,0 +1,451 @@
+use std::fmt::Display;
+
+/// End of the text in bracketed paste mode.
+const PASTE_END: &str = "\x1B[201~";
+/// Max length in bytes of the text in bracketed paste mode, the text is delivered once exceeded
+/// so a lost end marker can't swallow the following keys.
+const MAX_PASTE_LEN: usize = 64 * 1024;
+
+/// A key decoded from the terminal input.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum KeyCode {
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+    /// Function key F1 to F12.
+    F(u8),
+    /// Text pasted at once, which is surrounded by `ESC[200~` and `ESC[201~` in bracketed paste mode.
+    Paste(String),
+}
+
+impl Display for KeyCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Char(' ') => write!(f, "Space"),
+            Self::Char(c) => write!(f, "{}", c),
+            Self::F(n) => write!(f, "F{}", n),
+            Self::Enter => write!(f, "Enter"),
+            Self::Tab => write!(f, "Tab"),
+            Self::Backspace => write!(f, "Backspace"),
+            Self::Escape => write!(f, "Escape"),
+            Self::Up => write!(f, "Up"),
+            Self::Down => write!(f, "Down"),
+            Self::Left => write!(f, "Left"),
+            Self::Right => write!(f, "Right"),
+            Self::Home => write!(f, "Home"),
+            Self::End => write!(f, "End"),
+            Self::Insert => write!(f, "Insert"),
+            Self::Delete => write!(f, "Delete"),
+            Self::PageUp => write!(f, "PageUp"),
+            Self::PageDown => write!(f, "PageDown"),
+            Self::Paste(_) => write!(f, "Paste"),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+}
+
+impl Modifiers {
+    /// Modifiers encoded in the parameter of CSI sequence, e.g. `5` of `ESC[1;5D` is Control.
+    #[inline]
+    fn from_param(param: u32) -> Self {
+        let bits = param.saturating_sub(1);
+        Self {
+            shift: bits & 1 != 0,
+            alt: bits & 2 != 0,
+            ctrl: bits & 4 != 0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KeyEvent {
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+impl KeyEvent {
+    #[inline]
+    pub fn new(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: Modifiers::default(),
+        }
+    }
+
+    #[inline]
+    pub fn with(code: KeyCode, modifiers: Modifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// The text typed by the key, `None` for the function keys and control combinations.
+    #[inline]
+    pub fn text(&self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(c) if !self.modifiers.ctrl && !self.modifiers.alt => Some(c),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum State {
+    #[default]
+    Ground,
+    /// Received `ESC`.
+    Escape,
+    /// Received `ESC[`.
+    Csi,
+    /// Received `ESCO`.
+    Ss3,
+    /// Received `ESC[200~`, waiting for `ESC[201~`.
+    Paste,
+}
+
+/// Streaming decoder turns the characters received from terminal into [`KeyEvent`]s,
+/// including the CSI (`ESC[`) and SS3 (`ESCO`) sequences of the special keys.
+#[derive(Debug, Default)]
+pub struct KeyDecoder {
+    state: State,
+    /// Parameter and intermediate bytes of the CSI sequence.
+    params: String,
+    /// Text received in bracketed paste mode.
+    paste: String,
+    /// Leading UTF-16 surrogate waiting for the trailing one.
+    high_surrogate: Option<u32>,
+}
+
+impl KeyDecoder {
+    /// Feed one character (Unicode scalar or UTF-16 surrogate),
+    /// return the key if a whole key was decoded.
+    pub fn feed(&mut self, c: u32) -> Option<KeyEvent> {
+        let c = match c {
+            0xD800..=0xDBFF => {
+                self.high_surrogate = Some(c);
+                return None;
+            }
+            0xDC00..=0xDFFF => {
+                let high = self.high_surrogate.take()?;
+                char::from_u32(0x10000 + ((high - 0xD800) << 10) + (c - 0xDC00))?
+            }
+            _ => {
+                self.high_surrogate = None;
+                char::from_u32(c)?
+            }
+        };
+
+        match self.state {
+            State::Ground => {
+                if c == '\x1B' {
+                    self.state = State::Escape;
+                    None
+                } else {
+                    Some(plain_key(c))
+                }
+            }
+            State::Escape => match c {
+                '[' => {
+                    self.state = State::Csi;
+                    self.params.clear();
+                    None
+                }
+                'O' => {
+                    self.state = State::Ss3;
+                    None
+                }
+                // Escape pressed twice, the second one may start a sequence.
+                '\x1B' => Some(KeyEvent::new(KeyCode::Escape)),
+                // Alt+<key> is represent by `ESC<key>`.
+                c => {
+                    self.state = State::Ground;
+                    let mut key = plain_key(c);
+                    key.modifiers.alt = true;
+                    Some(key)
+                }
+            },
+            State::Csi => match c {
+                '\x20'..='\x3F' => {
+                    self.params.push(c);
+                    None
+                }
+                '~' if self.params == "200" => {
+                    self.state = State::Paste;
+                    self.paste.clear();
+                    None
+                }
+                '\x40'..='\x7E' => {
+                    self.state = State::Ground;
+                    csi_key(&self.params, c)
+                }
+                // Malformed sequence.
+                _ => {
+                    self.state = State::Ground;
+                    None
+                }
+            },
+            State::Ss3 => {
+                self.state = State::Ground;
+                ss3_key(c)
+            }
+            State::Paste => {
+                // Control+C aborts the paste, in case the end marker is lost.
+                if c == '\x03' {
+                    self.state = State::Ground;
+                    self.paste.clear();
+                    return Some(plain_key(c));
+                }
+                self.paste.push(c);
+                let text = match self.paste.strip_suffix(PASTE_END) {
+                    Some(text) => text.to_string(),
+                    None if self.paste.len() >= MAX_PASTE_LEN => std::mem::take(&mut self.paste),
+                    None => return None,
+                };
+                self.state = State::Ground;
+                self.paste.clear();
+                Some(KeyEvent::new(KeyCode::Paste(text)))
+            }
+        }
+    }
+
+    /// Should be called after a whole chunk of input was received,
+    /// an escape without following characters represents a single Esc key press.
+    pub fn flush(&mut self) -> Option<KeyEvent> {
+        if self.state == State::Escape {
+            self.state = State::Ground;
+            return Some(KeyEvent::new(KeyCode::Escape));
+        }
+        None
+    }
+
+    #[inline]
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Text of the bracketed paste to be inserted into the input line. Terminals send the pasted line breaks as `\r`,
+/// so `\r\n` and `\r` are normalized to `\n`, tabs become spaces, and the trailing line breaks and
+/// other control characters are removed.
+pub fn paste_text(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\t', " ")
+        .trim_end_matches('\n')
+        .chars()
+        .filter(|&c| c == '\n' || !c.is_control())
+        .collect()
+}
+
+/// The key of a single character outside of escape sequences.
+fn plain_key(c: char) -> KeyEvent {
+    let ctrl = Modifiers {
+        ctrl: true,
+        ..Default::default()
+    };
+    match c {
+        // Line feed is Control+J rather than Enter, which is sent as carriage return.
+        '\r' => KeyEvent::new(KeyCode::Enter),
+        '\t' => KeyEvent::new(KeyCode::Tab),
+        '\x7F' | '\x08' => KeyEvent::new(KeyCode::Backspace),
+        '\x1B' => KeyEvent::new(KeyCode::Escape),
+        '\0' => KeyEvent::with(KeyCode::Char(' '), ctrl),
+        '\x01'..='\x1A' => KeyEvent::with(KeyCode::Char((b'a' + c as u8 - 1) as char), ctrl),
+        '\x1C'..='\x1F' => KeyEvent::with(KeyCode::Char((b'\\' + c as u8 - 0x1C) as char), ctrl),
+        c => KeyEvent::new(KeyCode::Char(c)),
+    }
+}
+
+fn csi_key(params: &str, final_byte: char) -> Option<KeyEvent> {
+    let mut numbers = params.split(';').map(|p| p.parse::<u32>().unwrap_or(0));
+    let first = numbers.next().unwrap_or(0);
+    let modifiers = Modifiers::from_param(numbers.next().unwrap_or(1));
+
+    let code = match final_byte {
+        'A' => KeyCode::Up,
+        'B' => KeyCode::Down,
+        'C' => KeyCode::Right,
+        'D' => KeyCode::Left,
+        'H' => KeyCode::Home,
+        'F' => KeyCode::End,
+        'P' => KeyCode::F(1),
+        'Q' => KeyCode::F(2),
+        'R' => KeyCode::F(3),
+        'S' => KeyCode::F(4),
+        'Z' => {
+            let modifiers = Modifiers {
+                shift: true,
+                ..modifiers
+            };
+            return Some(KeyEvent::with(KeyCode::Tab, modifiers));
+        }
+        '~' => match first {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            11..=15 => KeyCode::F((first - 10) as u8),
+            17..=21 => KeyCode::F((first - 11) as u8),
+            23 | 24 => KeyCode::F((first - 12) as u8),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(KeyEvent::with(code, modifiers))
+}
+
+fn ss3_key(c: char) -> Option<KeyEvent> {
+    let code = match c {
+        'A' => KeyCode::Up,
+        'B' => KeyCode::Down,
+        'C' => KeyCode::Right,
+        'D' => KeyCode::Left,
+        'H' => KeyCode::Home,
+        'F' => KeyCode::End,
+        'M' => KeyCode::Enter,
+        'P' => KeyCode::F(1),
+        'Q' => KeyCode::F(2),
+        'R' => KeyCode::F(3),
+        'S' => KeyCode::F(4),
+        _ => return None,
+    };
+    Some(KeyEvent::new(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tokenizer::split_commands;
+
+    fn decode(input: &str) -> Vec<KeyEvent> {
+        let mut decoder = KeyDecoder::default();
+        let mut keys: Vec<KeyEvent> = input
+            .chars()
+            .filter_map(|c| decoder.feed(c as u32))
+            .collect();
+        keys.extend(decoder.flush());
+        keys
+    }
+
+    #[test]
+    fn test_decode_keys() {
+        let ctrl = Modifiers {
+            ctrl: true,
+            ..Default::default()
+        };
+        let alt = Modifiers {
+            alt: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            decode("a\r\x03\x7F\t"),
+            vec![
+                KeyEvent::new(KeyCode::Char('a')),
+                KeyEvent::new(KeyCode::Enter),
+                KeyEvent::with(KeyCode::Char('c'), ctrl),
+                KeyEvent::new(KeyCode::Backspace),
+                KeyEvent::new(KeyCode::Tab),
+            ]
+        );
+        assert_eq!(
+            decode("\x1B[A\x1BOB\x1B[1;5D\x1B[3~\x1B[5~\x1B[6;2~"),
+            vec![
+                KeyEvent::new(KeyCode::Up),
+                KeyEvent::new(KeyCode::Down),
+                KeyEvent::with(KeyCode::Left, ctrl),
+                KeyEvent::new(KeyCode::Delete),
+                KeyEvent::new(KeyCode::PageUp),
+                KeyEvent::with(
+                    KeyCode::PageDown,
+                    Modifiers {
+                        shift: true,
+                        ..Default::default()
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            decode("\x1BOP\x1B[15~\x1B[24~\x1B[1;3S"),
+            vec![
+                KeyEvent::new(KeyCode::F(1)),
+                KeyEvent::new(KeyCode::F(5)),
+                KeyEvent::new(KeyCode::F(12)),
+                KeyEvent::with(KeyCode::F(4), alt),
+            ]
+        );
+        assert_eq!(
+            decode("\x1Bf\x1B"),
+            vec![
+                KeyEvent::with(KeyCode::Char('f'), alt),
+                KeyEvent::new(KeyCode::Escape),
+            ]
+        );
+        assert_eq!(
+            decode("\u{4E2D}\x1B[99~x"),
+            vec![
+                KeyEvent::new(KeyCode::Char('\u{4E2D}')),
+                KeyEvent::new(KeyCode::Char('x')),
+            ]
+        );
+
+        assert_eq!(
+            decode("\x1B[200~a\r\x1B[Ab\x1B[201~\r"),
+            vec![
+                KeyEvent::new(KeyCode::Paste("a\r\x1B[Ab".to_string())),
+                KeyEvent::new(KeyCode::Enter),
+            ]
+        );
+
+        assert_eq!(
+            decode("\x1B[200~ab\x03c"),
+            vec![
+                KeyEvent::with(KeyCode::Char('c'), ctrl),
+                KeyEvent::new(KeyCode::Char('c')),
+            ]
+        );
+        let long = "a".repeat(MAX_PASTE_LEN);
+        assert_eq!(
+            decode(&format!("\x1B[200~{}b", long)),
+            vec![
+                KeyEvent::new(KeyCode::Paste(long)),
+                KeyEvent::new(KeyCode::Char('b')),
+            ]
+        );
+
+        let mut decoder = KeyDecoder::default();
+        assert_eq!(decoder.feed(0xD83D), None);
+        assert_eq!(
+            decoder.feed(0xDE00),
+            Some(KeyEvent::new(KeyCode::Char('\u{1F600}')))
+        );
+    }
+
+    #[test]
+    fn test_paste_text() {
+        assert_eq!(paste_text("a\rb\r\n\tc\x1B[A\r\r"), "a\nb\n c[A");
+        assert_eq!(paste_text("\r\n"), "");
+
+        let commands = split_commands(&paste_text("a\rb"), &|_| None).unwrap();
+        let words: Vec<Vec<String>> = commands.into_iter().map(|c| c.words).collect();
+        assert_eq!(words, vec![vec!["a"], vec!["b"]]);
+    }
+}
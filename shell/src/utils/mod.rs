@@ -1,3 +1,8 @@
 pub mod ansi_string;
 pub mod charmap;
 pub mod color256;
+pub mod highlight;
+pub mod key;
+pub mod options;
+pub mod prompt;
+pub mod tokenizer;
@@ -0,0 +1,541 @@
+use std::fmt::Display;
+
+/// Type of the option value.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OptionKind {
+    Int,
+    Float,
+    Bool,
+    String,
+    NodePath,
+    /// One of the [`choices`](OptionSpec::choices).
+    Enum,
+}
+
+impl Display for OptionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int => write!(f, "int"),
+            Self::Float => write!(f, "float"),
+            Self::Bool => write!(f, "bool"),
+            Self::String => write!(f, "string"),
+            Self::NodePath => write!(f, "path"),
+            Self::Enum => write!(f, "enum"),
+        }
+    }
+}
+
+/// Declaration of a command parameter.
+///
+/// A positional option takes the parameters not belonging to any flag in order,
+/// other options are given by `--name [value]` or `-s [value]`,
+/// and the value of a bool option can be omitted, e.g. `--force`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OptionSpec {
+    pub name: String,
+    pub short: Option<char>,
+    pub kind: OptionKind,
+    pub positional: bool,
+    pub required: bool,
+    /// Value used when the option is not given.
+    pub default: Option<String>,
+    pub choices: Vec<String>,
+    pub description: String,
+}
+
+impl OptionSpec {
+    #[inline]
+    fn takes_value(&self) -> bool {
+        self.kind != OptionKind::Bool
+    }
+
+    /// The value placeholder displayed in usage, e.g. `<count>`.
+    fn placeholder(&self) -> String {
+        match self.kind {
+            OptionKind::Enum if !self.choices.is_empty() => self.choices.join("|"),
+            _ if self.positional => self.name.clone(),
+            kind => kind.to_string(),
+        }
+    }
+
+    /// The flags of the option displayed in help, e.g. `-c, --count <int>`.
+    fn flags(&self) -> String {
+        if self.positional {
+            return format!("<{}>", self.placeholder());
+        }
+
+        let mut flags = match self.short {
+            Some(short) => format!("-{}, --{}", short, self.name),
+            None => format!("--{}", self.name),
+        };
+        if self.takes_value() {
+            flags.push_str(&format!(" <{}>", self.placeholder()));
+        }
+        flags
+    }
+}
+
+/// Typed value of the option.
+#[derive(Debug, PartialEq, Clone)]
+pub enum OptionValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    NodePath(String),
+}
+
+/// Result of [`parse_options`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParsedOptions {
+    /// `--help` is given.
+    Help,
+    /// Values of the options in declaration order, `None` if the option is not given and has no default.
+    Values(Vec<(String, Option<OptionValue>)>),
+}
+
+/// Errors that can occur while parsing the parameters against the options.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum OptionError {
+    UnknownOption(String),
+    MissingValue(String),
+    InvalidValue {
+        name: String,
+        value: String,
+        expected: String,
+    },
+    MissingRequired(String),
+    UnexpectedParameter(String),
+}
+
+impl Display for OptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownOption(option) => write!(f, "unknown option `{}`", option),
+            Self::MissingValue(name) => write!(f, "option `{}` requires a value", name),
+            Self::InvalidValue {
+                name,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value `{}` of `{}`, expected {}",
+                value, name, expected
+            ),
+            Self::MissingRequired(name) => write!(f, "missing required option `{}`", name),
+            Self::UnexpectedParameter(param) => write!(f, "unexpected parameter `{}`", param),
+        }
+    }
+}
+
+/// Parse the parameters against the options.
+///
+/// `--` stops the flag parsing, the following parameters are all positional.
+pub fn parse_options(
+    specs: &[OptionSpec],
+    params: &[String],
+) -> Result<ParsedOptions, OptionError> {
+    let mut values: Vec<Option<OptionValue>> = vec![None; specs.len()];
+    let mut positionals = specs
+        .iter()
+        .enumerate()
+        .filter(|(_, spec)| spec.positional)
+        .map(|(idx, _)| idx);
+    let mut params = params.iter();
+    let mut flags_end = false;
+
+    while let Some(param) = params.next() {
+        if !flags_end && param == "--" {
+            flags_end = true;
+            continue;
+        }
+        if !flags_end && param == "--help" {
+            return Ok(ParsedOptions::Help);
+        }
+
+        let Some((idx, inline)) = (if flags_end {
+            None
+        } else {
+            find_flag(specs, param)?
+        }) else {
+            let idx = positionals
+                .next()
+                .ok_or_else(|| OptionError::UnexpectedParameter(param.clone()))?;
+            values[idx] = Some(parse_value(&specs[idx], param)?);
+            continue;
+        };
+
+        let spec = &specs[idx];
+        let value = match inline {
+            Some(value) => value,
+            None if !spec.takes_value() => "true",
+            None => params
+                .next()
+                .ok_or_else(|| OptionError::MissingValue(param.clone()))?,
+        };
+        values[idx] = Some(parse_value(spec, value)?);
+    }
+
+    let mut parsed = Vec::with_capacity(specs.len());
+    for (spec, value) in specs.iter().zip(values) {
+        let value = match (value, &spec.default) {
+            (Some(value), _) => Some(value),
+            (None, Some(default)) => Some(parse_value(spec, default)?),
+            (None, None) if spec.required => {
+                return Err(OptionError::MissingRequired(spec.name.clone()));
+            }
+            (None, None) if !spec.positional && spec.kind == OptionKind::Bool => {
+                Some(OptionValue::Bool(false))
+            }
+            (None, None) => None,
+        };
+        parsed.push((spec.name.clone(), value));
+    }
+    Ok(ParsedOptions::Values(parsed))
+}
+
+/// Candidates of the parameter at `index`: the flags if it starts with `-`,
+/// otherwise the choices of the option it belongs to.
+pub fn complete_options(specs: &[OptionSpec], params: &[String], index: usize) -> Vec<String> {
+    let Some(prefix) = params.get(index) else {
+        return vec![];
+    };
+    if prefix.starts_with('-') {
+        return specs
+            .iter()
+            .filter(|spec| !spec.positional)
+            .map(|spec| format!("--{}", spec.name))
+            .chain(["--help".to_string()])
+            .collect();
+    }
+
+    let mut positional = 0;
+    let mut params = params[..index].iter();
+    let mut owner = None;
+    while let Some(param) = params.next() {
+        owner = None;
+        match find_flag(specs, param) {
+            Ok(Some((idx, None))) if specs[idx].takes_value() => {
+                if params.next().is_none() {
+                    owner = Some(idx);
+                }
+            }
+            Ok(Some(_)) | Err(_) => {}
+            Ok(None) => positional += 1,
+        }
+    }
+    let owner = owner.or_else(|| {
+        specs
+            .iter()
+            .enumerate()
+            .filter(|(_, spec)| spec.positional)
+            .nth(positional)
+            .map(|(idx, _)| idx)
+    });
+
+    match owner.map(|idx| &specs[idx]) {
+        Some(spec) if spec.kind == OptionKind::Enum => spec.choices.clone(),
+        Some(spec) if spec.kind == OptionKind::Bool => {
+            vec!["true".to_string(), "false".to_string()]
+        }
+        _ => vec![],
+    }
+}
+
+/// The usage generated from the options, e.g. `spawn <name> [count] [--force]`.
+pub fn options_usage(command: &str, specs: &[OptionSpec]) -> String {
+    let mut usage = command.to_string();
+    for spec in specs {
+        let option = match (spec.positional, spec.required) {
+            (true, true) => format!("<{}>", spec.placeholder()),
+            (true, false) => format!("[{}]", spec.placeholder()),
+            (false, required) => {
+                let flag = if spec.takes_value() {
+                    format!("--{} <{}>", spec.name, spec.placeholder())
+                } else {
+                    format!("--{}", spec.name)
+                };
+                if required {
+                    flag
+                } else {
+                    format!("[{}]", flag)
+                }
+            }
+        };
+        usage.push(' ');
+        usage.push_str(&option);
+    }
+    usage
+}
+
+/// Rows of the options table displayed in help, represent as (flags, description).
+pub fn options_rows(specs: &[OptionSpec]) -> Vec<(String, String)> {
+    specs
+        .iter()
+        .map(|spec| {
+            let mut description = spec
+                .description
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            if spec.required {
+                description.push_str(" (required)");
+            } else if let Some(default) = &spec.default {
+                description.push_str(&format!(" (default: {})", default));
+            }
+            (spec.flags(), description.trim_start().to_string())
+        })
+        .collect()
+}
+
+/// Find the option of the flag parameter, return the index and the inline value of `--name=value`.
+///
+/// Return `None` if the parameter is not a flag, negative numbers like `-1` are not flags.
+fn find_flag<'a>(
+    specs: &[OptionSpec],
+    param: &'a str,
+) -> Result<Option<(usize, Option<&'a str>)>, OptionError> {
+    let (flag, inline) = match param.split_once('=') {
+        Some((flag, value)) => (flag, Some(value)),
+        None => (param, None),
+    };
+
+    let found = if let Some(name) = flag.strip_prefix("--") {
+        specs
+            .iter()
+            .position(|spec| !spec.positional && spec.name == name)
+    } else if let Some(short) = flag.strip_prefix('-') {
+        let mut chars = short.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => return Ok(None),
+            (Some(c), _) if c.is_ascii_digit() || c == '.' => return Ok(None),
+            (Some(c), None) => specs
+                .iter()
+                .position(|spec| !spec.positional && spec.short == Some(c)),
+            _ => None,
+        }
+    } else {
+        return Ok(None);
+    };
+
+    match found {
+        Some(idx) => Ok(Some((idx, inline))),
+        None => Err(OptionError::UnknownOption(flag.to_string())),
+    }
+}
+
+fn parse_value(spec: &OptionSpec, value: &str) -> Result<OptionValue, OptionError> {
+    let invalid = |expected: String| OptionError::InvalidValue {
+        name: spec.name.clone(),
+        value: value.to_string(),
+        expected,
+    };
+
+    match spec.kind {
+        OptionKind::Int => value
+            .parse()
+            .map(OptionValue::Int)
+            .map_err(|_| invalid("an integer".to_string())),
+        OptionKind::Float => value
+            .parse()
+            .map(OptionValue::Float)
+            .map_err(|_| invalid("a number".to_string())),
+        OptionKind::Bool => match value.to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(OptionValue::Bool(true)),
+            "false" | "0" | "no" | "off" => Ok(OptionValue::Bool(false)),
+            _ => Err(invalid("`true` or `false`".to_string())),
+        },
+        OptionKind::String => Ok(OptionValue::String(value.to_string())),
+        OptionKind::NodePath => {
+            if value.is_empty() {
+                Err(invalid("a node path".to_string()))
+            } else {
+                Ok(OptionValue::NodePath(value.to_string()))
+            }
+        }
+        OptionKind::Enum => {
+            if spec.choices.iter().any(|choice| choice == value) {
+                Ok(OptionValue::String(value.to_string()))
+            } else {
+                let choices: Vec<String> =
+                    spec.choices.iter().map(|c| format!("`{}`", c)).collect();
+                Err(invalid(format!("one of {}", choices.join(", "))))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, kind: OptionKind) -> OptionSpec {
+        OptionSpec {
+            name: name.to_string(),
+            short: None,
+            kind,
+            positional: false,
+            required: false,
+            default: None,
+            choices: vec![],
+            description: String::new(),
+        }
+    }
+
+    fn specs() -> Vec<OptionSpec> {
+        vec![
+            OptionSpec {
+                positional: true,
+                required: true,
+                ..spec("name", OptionKind::String)
+            },
+            OptionSpec {
+                positional: true,
+                default: Some("1".to_string()),
+                ..spec("count", OptionKind::Int)
+            },
+            OptionSpec {
+                short: Some('f'),
+                ..spec("force", OptionKind::Bool)
+            },
+            OptionSpec {
+                short: Some('s'),
+                ..spec("scale", OptionKind::Float)
+            },
+            OptionSpec {
+                choices: vec!["easy".to_string(), "hard".to_string()],
+                default: Some("easy".to_string()),
+                ..spec("mode", OptionKind::Enum)
+            },
+            spec("target", OptionKind::NodePath),
+        ]
+    }
+
+    fn params(params: &[&str]) -> Vec<String> {
+        params.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let specs = specs();
+
+        let parsed = parse_options(&specs, &params(&["orc", "-s", "1.5", "--force", "-3"]));
+        assert_eq!(
+            parsed,
+            Ok(ParsedOptions::Values(vec![
+                (
+                    "name".to_string(),
+                    Some(OptionValue::String("orc".to_string()))
+                ),
+                ("count".to_string(), Some(OptionValue::Int(-3))),
+                ("force".to_string(), Some(OptionValue::Bool(true))),
+                ("scale".to_string(), Some(OptionValue::Float(1.5))),
+                (
+                    "mode".to_string(),
+                    Some(OptionValue::String("easy".to_string()))
+                ),
+                ("target".to_string(), None),
+            ]))
+        );
+
+        let parsed = parse_options(
+            &specs,
+            &params(&["--mode=hard", "--target", "/root/Player", "--", "--orc"]),
+        );
+        assert_eq!(
+            parsed,
+            Ok(ParsedOptions::Values(vec![
+                (
+                    "name".to_string(),
+                    Some(OptionValue::String("--orc".to_string()))
+                ),
+                ("count".to_string(), Some(OptionValue::Int(1))),
+                ("force".to_string(), Some(OptionValue::Bool(false))),
+                ("scale".to_string(), None),
+                (
+                    "mode".to_string(),
+                    Some(OptionValue::String("hard".to_string()))
+                ),
+                (
+                    "target".to_string(),
+                    Some(OptionValue::NodePath("/root/Player".to_string()))
+                ),
+            ]))
+        );
+
+        assert_eq!(
+            parse_options(&specs, &params(&["orc", "--help"])),
+            Ok(ParsedOptions::Help)
+        );
+    }
+
+    #[test]
+    fn test_parse_options_error() {
+        let specs = specs();
+
+        for (input, error) in [
+            (vec![], OptionError::MissingRequired("name".to_string())),
+            (
+                vec!["orc", "--fly"],
+                OptionError::UnknownOption("--fly".to_string()),
+            ),
+            (
+                vec!["orc", "-s"],
+                OptionError::MissingValue("-s".to_string()),
+            ),
+            (
+                vec!["orc", "2", "3"],
+                OptionError::UnexpectedParameter("3".to_string()),
+            ),
+            (
+                vec!["orc", "two"],
+                OptionError::InvalidValue {
+                    name: "count".to_string(),
+                    value: "two".to_string(),
+                    expected: "an integer".to_string(),
+                },
+            ),
+            (
+                vec!["orc", "--mode", "normal"],
+                OptionError::InvalidValue {
+                    name: "mode".to_string(),
+                    value: "normal".to_string(),
+                    expected: "one of `easy`, `hard`".to_string(),
+                },
+            ),
+        ] {
+            assert_eq!(parse_options(&specs, &params(&input)), Err(error));
+        }
+    }
+
+    #[test]
+    fn test_complete_options() {
+        let specs = specs();
+
+        assert_eq!(
+            complete_options(&specs, &params(&["orc", "--"]), 1),
+            params(&["--force", "--scale", "--mode", "--target", "--help"])
+        );
+        assert_eq!(
+            complete_options(&specs, &params(&["orc", "--mode", ""]), 2),
+            params(&["easy", "hard"])
+        );
+        assert_eq!(
+            complete_options(&specs, &params(&["orc", "-f", ""]), 2),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_options_usage() {
+        let specs = specs();
+
+        assert_eq!(
+            options_usage("spawn", &specs),
+            "spawn <name> [count] [--force] [--scale <float>] [--mode <easy|hard>] [--target <path>]"
+        );
+        assert_eq!(options_rows(&specs)[2].0, "-f, --force");
+        assert_eq!(options_rows(&specs)[1].1, "(default: 1)");
+    }
+}
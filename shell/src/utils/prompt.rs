@@ -0,0 +1,94 @@
+/// Render the prompt template, `{name}` is replaced by `lookup(name)` or the style of the color markup:
+///
+/// - `{black}`, `{red}`, `{green}`, `{yellow}`, `{blue}`, `{magenta}`, `{cyan}`, `{white}`
+///   and their `{bright_*}` variants set the foreground color.
+/// - `{bold}`, `{dim}`, `{italic}`, `{underline}` set the text style, `{reset}` clears all the styles.
+///
+/// Unknown placeholders are kept as they are, `{{` and `}}` represent literal braces.
+pub fn render_prompt(template: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        rendered.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            rendered.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+        if rest.starts_with('}') {
+            rendered.push('}');
+            rest = &rest[1..];
+            continue;
+        }
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        let name = &rest[1..end];
+        match markup(name).or_else(|| lookup(name)) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Escape sequence of the color markup.
+fn markup(name: &str) -> Option<String> {
+    const COLORS: [&str; 8] = [
+        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+    ];
+
+    let style = match name {
+        "reset" => "\x1B[0m",
+        "bold" => "\x1B[1m",
+        "dim" => "\x1B[2m",
+        "italic" => "\x1B[3m",
+        "underline" => "\x1B[4m",
+        _ => {
+            let (bright, color) = match name.strip_prefix("bright_") {
+                Some(color) => (8, color),
+                None => (0, name),
+            };
+            let index = COLORS.iter().position(|&c| c == color)?;
+            return Some(format!("\x1B[38;5;{}m", index + bright));
+        }
+    };
+    Some(style.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prompt() {
+        let lookup = |name: &str| match name {
+            "host" => Some("game".to_string()),
+            "scene" => Some("Level1".to_string()),
+            "paused" => Some(String::new()),
+            _ => None,
+        };
+
+        assert_eq!(render_prompt("", &lookup), "");
+        assert_eq!(render_prompt("{host}> ", &lookup), "game> ");
+        assert_eq!(
+            render_prompt("{host}:{scene}{paused}$ ", &lookup),
+            "game:Level1$ "
+        );
+        assert_eq!(
+            render_prompt("{green}{host}{reset}{bright_red}!", &lookup),
+            "\x1B[38;5;2mgame\x1B[0m\x1B[38;5;9m!"
+        );
+        assert_eq!(
+            render_prompt("{{host}} {unknown} {host", &lookup),
+            "{host} {unknown} {host"
+        );
+    }
+}
@@ -0,0 +1,673 @@
+use std::{fmt::Display, iter::Peekable, str::CharIndices};
+
+/// Errors that can occur while splitting a command line into words.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TokenizeError {
+    /// A quoted string was not closed before the end of input, carries the quote character.
+    UnclosedQuote(char),
+    /// The input ends with a backslash that escapes nothing.
+    TrailingBackslash,
+    /// An operator appears where a command is expected, e.g. `&& a` or `a && || b`.
+    UnexpectedOperator(Operator),
+    /// The input ends with an operator which requires a following command, e.g. `a &&`.
+    MissingCommand(Operator),
+}
+
+impl Display for TokenizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnclosedQuote(quote) => write!(f, "missing closing quote `{}`", quote),
+            Self::TrailingBackslash => write!(f, "unexpected end of input after `\\`"),
+            Self::UnexpectedOperator(op) => write!(f, "unexpected `{}`", op),
+            Self::MissingCommand(op) => write!(f, "missing command after `{}`", op),
+        }
+    }
+}
+
+/// Control operators to chain multiple commands in one line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Operator {
+    /// `;`, run the next command unconditionally.
+    Sequence,
+    /// `&&`, run the next command only if the previous one succeeded.
+    And,
+    /// `||`, run the next command only if the previous one failed.
+    Or,
+    /// `&` at the end of a line, run the previous command in the background.
+    Background,
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sequence => write!(f, ";"),
+            Self::And => write!(f, "&&"),
+            Self::Or => write!(f, "||"),
+            Self::Background => write!(f, "&"),
+        }
+    }
+}
+
+/// A single command of the command line.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChainedCommand {
+    /// The operator between the previous command and this one, [`Operator::Sequence`] for the first command.
+    pub connector: Operator,
+    pub words: Vec<String>,
+    /// The command is followed by [`Operator::Background`].
+    pub background: bool,
+}
+
+/// A word of the command line.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Token {
+    /// The word with quotes and escapes removed.
+    pub text: String,
+    /// Byte offset of the start of the raw word in the input.
+    pub start: usize,
+    /// Byte offset of the end of the raw word in the input.
+    pub end: usize,
+    /// `Some` if the token is an unquoted control operator rather than a word.
+    pub operator: Option<Operator>,
+}
+
+/// Split the command line into words with shell-like quoting rules:
+///
+/// - Words are separated by runs of whitespace.
+/// - Text inside single quotes is taken literally.
+/// - Inside double quotes, a backslash only escapes `"` and `\`.
+/// - Outside of quotes, a backslash escapes any following character.
+/// - Outside of single quotes, a backslash followed by a line break is removed to join the lines.
+/// - Adjacent quoted and unquoted parts are joined into a single word, so `""` gives an empty word.
+/// - Unquoted control operators `;`, `&&` and `||` are separate words even without whitespace around.
+/// - An unquoted `&` at the end of a line is the control operator `&`, other single `&` and `|` are normal characters.
+/// - Unquoted line breaks separate commands like `;`, except the ones following an operator or another line break.
+pub fn tokenize(input: &str) -> Result<Vec<String>, TokenizeError> {
+    Ok(lex(input, false, &no_expand)?
+        .into_iter()
+        .map(|t| t.text)
+        .collect())
+}
+
+/// Split the command line into commands chained by control operators, see [`tokenize`] for quoting rules.
+///
+/// Parameters like `$?` outside of single quotes are replaced by the value returned from `expand`.
+/// Unquoted values are split into words on whitespace, the first and the last words join the adjacent text,
+/// e.g. `tp $pos` gives two arguments if `pos` is `10 20`; values inside double quotes stay in one word.
+/// Parameters that `expand` returns `None` are kept as is.
+pub fn split_commands(
+    input: &str,
+    expand: &dyn Fn(&str) -> Option<String>,
+) -> Result<Vec<ChainedCommand>, TokenizeError> {
+    let mut commands = vec![];
+    let mut words = vec![];
+    let mut connector = Operator::Sequence;
+
+    for token in lex(input, false, expand)? {
+        match token.operator {
+            None => words.push(token.text),
+            Some(op) => {
+                if words.is_empty() {
+                    return Err(TokenizeError::UnexpectedOperator(op));
+                }
+                commands.push(ChainedCommand {
+                    connector,
+                    words: std::mem::take(&mut words),
+                    background: op == Operator::Background,
+                });
+                // The command after a background one runs unconditionally.
+                connector = match op {
+                    Operator::Background => Operator::Sequence,
+                    op => op,
+                };
+            }
+        }
+    }
+
+    if !words.is_empty() {
+        commands.push(ChainedCommand {
+            connector,
+            words,
+            background: false,
+        });
+    } else if connector != Operator::Sequence {
+        return Err(TokenizeError::MissingCommand(connector));
+    }
+
+    Ok(commands)
+}
+
+/// Same as [`tokenize`], but the incomplete input is accepted:
+/// unclosed quotes are closed at the end of input, and the trailing backslash is ignored.
+///
+/// Used to inspect the line while it is still being edited, e.g. completion.
+pub fn tokenize_partial(input: &str) -> Vec<Token> {
+    lex(input, true, &no_expand).unwrap_or_default()
+}
+
+/// Quote the word if needed, so that [`tokenize`] gives back the same word.
+pub fn quote(word: &str) -> String {
+    if !word.is_empty()
+        && !word
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '\'' | '"' | '\\' | ';' | '&' | '|'))
+    {
+        return word.to_string();
+    }
+
+    let mut quoted = String::with_capacity(word.len() + 2);
+    quoted.push('"');
+    for c in word.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Determines if there is only whitespace before the next line break or the end of input.
+#[inline]
+fn is_line_end(rest: &str) -> bool {
+    rest.lines().next().unwrap_or_default().trim().is_empty()
+}
+
+#[inline]
+fn no_expand(_: &str) -> Option<String> {
+    None
+}
+
+fn lex(
+    input: &str,
+    partial: bool,
+    expand: &dyn Fn(&str) -> Option<String>,
+) -> Result<Vec<Token>, TokenizeError> {
+    let mut tokens = vec![];
+    let mut word = String::new();
+    // Byte offset where the current word starts, `Some` even for empty quoted strings.
+    let mut start = None;
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' => {
+                start.get_or_insert(i);
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) => break,
+                        Some((_, c)) => word.push(c),
+                        None if partial => break,
+                        None => return Err(TokenizeError::UnclosedQuote('\'')),
+                    }
+                }
+            }
+            '"' => {
+                start.get_or_insert(i);
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c @ ('"' | '\\'))) => word.push(c),
+                            // Line continuation.
+                            Some((_, '\n')) => {}
+                            Some((_, c)) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None if partial => break,
+                            None => return Err(TokenizeError::UnclosedQuote('"')),
+                        },
+                        Some((_, '$')) => {
+                            if let Some(value) = expand_parameter(&mut chars, &mut word, expand) {
+                                word.push_str(&value);
+                            }
+                        }
+                        Some((_, c)) => word.push(c),
+                        None if partial => break,
+                        None => return Err(TokenizeError::UnclosedQuote('"')),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // Line continuation.
+                Some((_, '\n')) => {}
+                Some((_, c)) => {
+                    start.get_or_insert(i);
+                    word.push(c);
+                }
+                None if partial => {
+                    start.get_or_insert(i);
+                }
+                None => return Err(TokenizeError::TrailingBackslash),
+            },
+            '\n' => {
+                if let Some(start) = start.take() {
+                    tokens.push(Token {
+                        text: std::mem::take(&mut word),
+                        start,
+                        end: i,
+                        operator: None,
+                    });
+                }
+                // Blank lines and the line breaks after operators, e.g. `a &&\nb`, are plain whitespace.
+                if tokens.last().is_some_and(|t| t.operator.is_none()) {
+                    tokens.push(Token {
+                        text: Operator::Sequence.to_string(),
+                        start: i,
+                        end: i + 1,
+                        operator: Some(Operator::Sequence),
+                    });
+                }
+            }
+            c if c.is_whitespace() => {
+                if let Some(start) = start.take() {
+                    tokens.push(Token {
+                        text: std::mem::take(&mut word),
+                        start,
+                        end: i,
+                        operator: None,
+                    });
+                }
+            }
+            ';' | '&' | '|' => {
+                let (operator, len) = match (c, chars.peek()) {
+                    (';', _) => (Operator::Sequence, 1),
+                    ('&', Some((_, '&'))) => (Operator::And, 2),
+                    ('|', Some((_, '|'))) => (Operator::Or, 2),
+                    ('&', _) if is_line_end(&input[i + 1..]) => (Operator::Background, 1),
+                    // Other single `&` and `|` are not supported operators, treat them as normal characters.
+                    _ => {
+                        start.get_or_insert(i);
+                        word.push(c);
+                        continue;
+                    }
+                };
+                if len == 2 {
+                    chars.next();
+                }
+
+                if let Some(start) = start.take() {
+                    tokens.push(Token {
+                        text: std::mem::take(&mut word),
+                        start,
+                        end: i,
+                        operator: None,
+                    });
+                }
+                tokens.push(Token {
+                    text: operator.to_string(),
+                    start: i,
+                    end: i + len,
+                    operator: Some(operator),
+                });
+            }
+            '$' => {
+                let Some(value) = expand_parameter(&mut chars, &mut word, expand) else {
+                    start.get_or_insert(i);
+                    continue;
+                };
+                // The unquoted value is split into words, an empty value adds no word on its own.
+                let end = chars.peek().map_or(input.len(), |&(j, _)| j);
+                for (n, field) in value.split(char::is_whitespace).enumerate() {
+                    // Every whitespace ends the current word.
+                    if let Some(start) = start.take_if(|_| n > 0) {
+                        tokens.push(Token {
+                            text: std::mem::take(&mut word),
+                            start,
+                            end,
+                            operator: None,
+                        });
+                    }
+                    if !field.is_empty() {
+                        start.get_or_insert(i);
+                        word.push_str(field);
+                    }
+                }
+            }
+            c => {
+                start.get_or_insert(i);
+                word.push(c);
+            }
+        }
+    }
+
+    if let Some(start) = start {
+        tokens.push(Token {
+            text: word,
+            start,
+            end: input.len(),
+            operator: None,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Determines if the text is a valid variable name, which matches `[A-Za-z_][A-Za-z0-9_]*`.
+pub fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Determines if the text is a valid alias name, which is a plain word
+/// without quotes, escapes, control operators, `=` or `$`.
+pub fn is_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.chars().any(|c| {
+            c.is_whitespace() || matches!(c, '\'' | '"' | '\\' | ';' | '&' | '|' | '=' | '$')
+        })
+}
+
+/// Replace the first word of every chained command with its alias by `lookup`.
+///
+/// Only unquoted words are expanded, and the alias value is expanded recursively,
+/// except for the aliases already being expanded, so self-referencing aliases like
+/// `ls="ls -a"` are allowed.
+pub fn expand_aliases(input: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    expand_aliases_guarded(input, lookup, &mut vec![])
+}
+
+fn expand_aliases_guarded(
+    input: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    expanding: &mut Vec<String>,
+) -> String {
+    let mut output = String::new();
+    let mut last = 0;
+    let mut command_start = true;
+
+    for token in tokenize_partial(input) {
+        if token.operator.is_some() {
+            command_start = true;
+            continue;
+        }
+        if !std::mem::take(&mut command_start)
+            || input[token.start..token.end] != token.text
+            || expanding.contains(&token.text)
+        {
+            continue;
+        }
+        let Some(value) = lookup(&token.text) else {
+            continue;
+        };
+
+        expanding.push(token.text);
+        output.push_str(&input[last..token.start]);
+        output.push_str(&expand_aliases_guarded(&value, lookup, expanding));
+        expanding.pop();
+        last = token.end;
+    }
+
+    output.push_str(&input[last..]);
+    output
+}
+
+/// Expand the parameter following `$`, which has already been consumed, return the expanded value.
+///
+/// Supported forms are `$?`, `$name` and `${name}`. A single `$` and the parameters that `expand`
+/// returns `None` are kept as is, which are pushed to `word` directly.
+fn expand_parameter(
+    chars: &mut Peekable<CharIndices>,
+    word: &mut String,
+    expand: &dyn Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let mut lookahead = chars.clone();
+    let mut name = String::new();
+    let braced = match lookahead.next() {
+        Some((_, '?')) => {
+            name.push('?');
+            false
+        }
+        Some((_, '{')) => {
+            loop {
+                match lookahead.next() {
+                    Some((_, '}')) => break,
+                    Some((_, c)) => name.push(c),
+                    None => {
+                        name.clear();
+                        break;
+                    }
+                }
+            }
+            true
+        }
+        Some((_, c)) if c.is_ascii_alphabetic() || c == '_' => {
+            name.push(c);
+            while let Some(&(_, c)) = lookahead.peek() {
+                if !c.is_ascii_alphanumeric() && c != '_' {
+                    break;
+                }
+                name.push(c);
+                lookahead.next();
+            }
+            false
+        }
+        _ => false,
+    };
+
+    if name != "?" && !is_variable_name(&name) {
+        word.push('$');
+        return None;
+    }
+    *chars = lookahead;
+
+    let value = expand(&name);
+    if value.is_none() {
+        word.push('$');
+        if braced {
+            word.push_str(&format!("{{{}}}", name));
+        } else {
+            word.push_str(&name);
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("").unwrap(), Vec::<String>::new());
+        assert_eq!(tokenize("   ").unwrap(), Vec::<String>::new());
+        assert_eq!(
+            tokenize("  spawn   orc  3 ").unwrap(),
+            vec!["spawn", "orc", "3"]
+        );
+        assert_eq!(
+            tokenize(r#"spawn "Big Orc" 3"#).unwrap(),
+            vec!["spawn", "Big Orc", "3"]
+        );
+        assert_eq!(
+            tokenize(r#"say 'He said "hi"' \"quoted\""#).unwrap(),
+            vec!["say", r#"He said "hi""#, r#""quoted""#]
+        );
+        assert_eq!(
+            tokenize(r#"path "a\"b\\c\n" x\ y"#).unwrap(),
+            vec!["path", r#"a"b\c\n"#, "x y"]
+        );
+        assert_eq!(tokenize(r#"set name """#).unwrap(), vec!["set", "name", ""]);
+        assert_eq!(tokenize(r#"a"b c"'d'"#).unwrap(), vec!["ab cd"]);
+        assert_eq!(
+            tokenize("give \\\nsword \"a\\\nb\" 'c\nd'").unwrap(),
+            vec!["give", "sword", "ab", "c\nd"]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_error() {
+        assert_eq!(
+            tokenize(r#"say "hello"#),
+            Err(TokenizeError::UnclosedQuote('"'))
+        );
+        assert_eq!(
+            tokenize("say 'hello"),
+            Err(TokenizeError::UnclosedQuote('\''))
+        );
+        assert_eq!(tokenize("say \\"), Err(TokenizeError::TrailingBackslash));
+    }
+
+    #[test]
+    fn test_split_commands() {
+        let commands =
+            split_commands("load_level 3 && godmode on;give 'a;b'||x", &no_expand).unwrap();
+        let connectors: Vec<Operator> = commands.iter().map(|c| c.connector).collect();
+        let words: Vec<Vec<String>> = commands.into_iter().map(|c| c.words).collect();
+        assert_eq!(
+            connectors,
+            vec![
+                Operator::Sequence,
+                Operator::And,
+                Operator::Sequence,
+                Operator::Or
+            ]
+        );
+        assert_eq!(
+            words,
+            vec![
+                vec!["load_level", "3"],
+                vec!["godmode", "on"],
+                vec!["give", "a;b"],
+                vec!["x"]
+            ]
+        );
+
+        assert_eq!(
+            split_commands("a & b", &no_expand).unwrap()[0].words,
+            vec!["a", "&", "b"]
+        );
+        assert_eq!(split_commands("a;", &no_expand).unwrap().len(), 1);
+        assert_eq!(
+            split_commands("log -f&\nb && c &", &no_expand)
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.connector, c.words.join(" "), c.background))
+                .collect::<Vec<_>>(),
+            vec![
+                (Operator::Sequence, "log -f".to_string(), true),
+                (Operator::Sequence, "b".to_string(), false),
+                (Operator::And, "c".to_string(), true),
+            ]
+        );
+        assert_eq!(
+            split_commands("\na 1\n\nb &&\n c\n", &no_expand)
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.connector, c.words))
+                .collect::<Vec<_>>(),
+            vec![
+                (Operator::Sequence, vec!["a".to_string(), "1".to_string()]),
+                (Operator::Sequence, vec!["b".to_string()]),
+                (Operator::And, vec!["c".to_string()]),
+            ]
+        );
+        assert_eq!(split_commands("", &no_expand).unwrap(), vec![]);
+        assert_eq!(
+            split_commands("&& a", &no_expand),
+            Err(TokenizeError::UnexpectedOperator(Operator::And))
+        );
+        assert_eq!(
+            split_commands("a || ; b", &no_expand),
+            Err(TokenizeError::UnexpectedOperator(Operator::Sequence))
+        );
+        assert_eq!(
+            split_commands("a ||", &no_expand),
+            Err(TokenizeError::MissingCommand(Operator::Or))
+        );
+        assert_eq!(
+            split_commands("a && &", &no_expand),
+            Err(TokenizeError::UnexpectedOperator(Operator::Background))
+        );
+    }
+
+    #[test]
+    fn test_expand_parameter() {
+        let expand = |name: &str| match name {
+            "?" => Some("127".to_string()),
+            "pos" => Some("1 2".to_string()),
+            "pad" => Some(" a  b ".to_string()),
+            "empty" => Some(String::new()),
+            _ => None,
+        };
+        let commands = split_commands(r#"echo $? "[$?]" '$?' \$? $ $x"#, &expand).unwrap();
+        assert_eq!(
+            commands[0].words,
+            vec!["echo", "127", "[127]", "$?", "$?", "$", "$x"]
+        );
+
+        let commands =
+            split_commands(r#"tp $pos "${pos}!" ${pos}_x $pos_x ${pos ${1}"#, &expand).unwrap();
+        assert_eq!(
+            commands[0].words,
+            vec![
+                "tp", "1", "2", "1 2!", "1", "2_x", "$pos_x", "${pos", "${1}"
+            ]
+        );
+
+        let commands =
+            split_commands(r#"tp "$pos" x${pad}y $empty "$empty" a$empty"#, &expand).unwrap();
+        assert_eq!(
+            commands[0].words,
+            vec!["tp", "1 2", "x", "a", "b", "y", "", "a"]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_partial() {
+        let tokens = tokenize_partial(r#"spawn "Big O"#);
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[1].text, "Big O");
+        assert_eq!((tokens[1].start, tokens[1].end), (6, 12));
+
+        let tokens = tokenize_partial("give  sword ");
+        assert_eq!(tokens.len(), 2);
+        assert_eq!((tokens[1].start, tokens[1].end), (6, 11));
+    }
+
+    #[test]
+    fn test_expand_aliases() {
+        let lookup = |name: &str| match name {
+            "gm" => Some("godmode on && give all".to_string()),
+            "ls" => Some("ls -a".to_string()),
+            "ll" => Some("ls -l".to_string()),
+            "a" => Some("b".to_string()),
+            "b" => Some("a".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            expand_aliases("gm; echo gm || gm x", &lookup),
+            "godmode on && give all; echo gm || godmode on && give all x"
+        );
+        assert_eq!(expand_aliases("ll dir", &lookup), "ls -a -l dir");
+        assert_eq!(expand_aliases("a", &lookup), "a");
+        assert_eq!(expand_aliases("'gm' \\gm", &lookup), "'gm' \\gm");
+        assert_eq!(expand_aliases("", &lookup), "");
+
+        assert!(is_alias_name("tp.home"));
+        assert!(!is_alias_name("a=b"));
+        assert!(!is_alias_name("a b"));
+        assert!(!is_alias_name(""));
+    }
+
+    #[test]
+    fn test_quote() {
+        for word in [
+            "sword",
+            "Big Orc",
+            r#"say "hi""#,
+            "a\\b",
+            "it's",
+            "a&&b",
+            "",
+        ] {
+            assert_eq!(tokenize(&quote(word)).unwrap(), vec![word]);
+        }
+        assert_eq!(quote("sword"), "sword");
+    }
+}
@@ -143,6 +143,13 @@ impl Pty for TermdotPty {
                     ));
                     self.send_ipc_data(IpcEvent::pack_terminal_version(terminal_version()));
                 }
+                IpcEvent::RequestExit => {
+                    // End of input in the shell (Ctrl+D).
+                    self.send_ipc_data(IpcEvent::Exit);
+                    self.running = false;
+                    self.closed = true;
+                    self.ipc_channel = None;
+                }
                 IpcEvent::Exit => {
                     self.running = false;
                     self.closed = true;